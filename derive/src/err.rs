@@ -9,6 +9,12 @@ pub enum VisitErr {
     CannotParseAsIdent(Span, String),
     #[error("Attribute must be a literal")]
     NotLitAttribute(Span),
+    #[error("Enum arenas cannot be concurrent")]
+    ConcurrentEnum(Span),
+    #[error("Enum arenas need at least one variant")]
+    EmptyEnum(Span),
+    #[error("Enum refs can only be packed into `u32` or `u64`")]
+    UnsupportedPacked(Span),
 }
 #[derive(Error, Debug, Clone)]
 pub enum GenerateErr {
@@ -37,6 +43,9 @@ impl IntoCompileError for VisitErr {
         match self {
             VisitErr::NotLitAttribute(s) => *s,
             VisitErr::CannotParseAsIdent(s, _) => *s,
+            VisitErr::ConcurrentEnum(s) => *s,
+            VisitErr::EmptyEnum(s) => *s,
+            VisitErr::UnsupportedPacked(s) => *s,
         }
     }
 }
@@ -0,0 +1,500 @@
+use proc_macro2::{Literal, TokenStream};
+use quote::{format_ident, quote};
+use syn::{Fields, Index, Variant};
+
+use crate::err::GenerateResult;
+use crate::gen::struct_ref::StructRefGenerator;
+use crate::gen::{snake_case, CodeGenerator, Env};
+use crate::meta::DataMetaInfo;
+
+pub struct EnumArenaGenerator;
+
+unsafe impl Sync for EnumArenaGenerator {}
+
+impl CodeGenerator for EnumArenaGenerator {
+    fn gen(&self, meta: &DataMetaInfo, env: &Env) -> GenerateResult<TokenStream> {
+        if meta.is_enum {
+            Self::enum_arena(meta, env)
+        } else {
+            Ok(TokenStream::new())
+        }
+    }
+
+    fn create() -> Box<dyn CodeGenerator>
+    where
+        Self: Sized,
+    {
+        Box::new(EnumArenaGenerator)
+    }
+}
+
+impl EnumArenaGenerator {
+    /// Pattern matching any value of `variant`.
+    fn pattern(meta: &DataMetaInfo, variant: &Variant) -> TokenStream {
+        let id = &meta.name;
+        let ident = &variant.ident;
+
+        match &variant.fields {
+            Fields::Named(_) => quote! { #id::#ident { .. } },
+            Fields::Unnamed(_) => quote! { #id::#ident(..) },
+            Fields::Unit => quote! { #id::#ident },
+        }
+    }
+
+    fn enum_arena(meta: &DataMetaInfo, env: &Env) -> GenerateResult<TokenStream> {
+        let vis = &meta.vis;
+        let id = &meta.name;
+        let ref_id = &meta.ref_id;
+        let mut_ref_id = &meta.mut_ref_id;
+        let arena_id = &meta.arena_id;
+        let arena_lifetime = StructRefGenerator::arena_lifetime();
+        let alloc_param = env
+            .alloc_param(meta, false)
+            .expect("non-concurrent storage takes an allocator");
+        let alloc_id = alloc_param.ident.clone();
+        let where_clause = &meta.generics.where_clause;
+        let ref_generic_args = meta.generic_args_token_stream(
+            Some(StructRefGenerator::arena_lifetime()),
+            Some(alloc_param.clone()),
+        );
+        let generic_args = meta.generic_args_token_stream(None, None);
+        let arena_generic_args = meta.generic_args_token_stream(None, Some(alloc_param.clone()));
+        let generics = meta.generics_token_steam(None, env.alloc_param(meta, true));
+        let generics_param = meta.generics_param_token_steam(None, None);
+        let arena_generics_param = meta.generics_param_token_steam(None, Some(alloc_param));
+
+        let arena = &env.arena;
+        let chunk_policy = &env.chunk_policy;
+        let arena_result = &env.arena_result;
+        let global = &env.global;
+        let once_cell = &env.once_cell;
+        let index = &env.index;
+        let tagged_idx = &env.tagged_idx;
+        let tagged_enum = &env.tagged_enum;
+        let (storage, storage_ref) = env.storage(meta);
+        let idx_id = &meta.idx_id;
+        let alias_generics = meta.alias_generics_token_stream();
+        let word = if meta.packed == "u64" {
+            &env.non_zero_u64
+        } else {
+            &env.non_zero_u32
+        };
+
+        let global_arena_path = quote! {#arena_id #generic_args};
+        let arena_path = quote! {#arena_id #arena_generic_args};
+        let path = quote! { #id #generic_args };
+        let ref_path = quote! { #ref_id #ref_generic_args };
+        let mut_ref_path = quote! { #mut_ref_id #ref_generic_args };
+        let storage_path = quote! { #storage<#path, #alloc_id> };
+        let ref_bounds = quote! { #path: #arena_lifetime, #alloc_id: #arena_lifetime };
+        let tagged_path = quote! { #tagged_idx<#path> };
+
+        // Variants with fields get a storage each, in declaration order. Unit variants need none:
+        // an immutable ref to one points to the value kept by the arena. Only a mutable ref, which
+        // needs a slot of its own, puts it into the storage shared by all unit variants, which is
+        // created on first use. Tags start at 1, so a packed ref is never zero.
+        let tags = (1..).map(Literal::u32_unsuffixed);
+        let (stored, units): (Vec<_>, Vec<_>) = tags
+            .zip(&meta.variants)
+            .partition(|(_, variant)| !matches!(variant.fields, Fields::Unit));
+        let patterns = meta.variants.iter().map(|v| Self::pattern(meta, v));
+        let variant_count = meta.variants.len() as u32;
+        let variant_tags = (1..=variant_count).map(Literal::u32_unsuffixed);
+        let stored_tags: Vec<_> = stored.iter().map(|(tag, _)| tag).collect();
+        let stored_fields: Vec<_> = (0..stored.len()).map(Index::from).collect();
+        let unit_tags: Vec<_> = units.iter().map(|(tag, _)| tag).collect();
+        let unit_fields: Vec<_> = (0..units.len()).map(Index::from).collect();
+        let unit_idents = units.iter().map(|(_, variant)| &variant.ident);
+
+        let storages = stored_fields
+            .iter()
+            .map(|field| quote! { self.storages.#field })
+            .collect::<Vec<_>>();
+        let (unit_fields_decl, unit_fields_init, unit_storage_arms, is_unit) = if units.is_empty() {
+            (
+                TokenStream::new(),
+                TokenStream::new(),
+                (TokenStream::new(), TokenStream::new()),
+                quote!(false),
+            )
+        } else {
+            (
+                quote! {
+                    policy: #chunk_policy,
+                    alloc: #alloc_id,
+                    units: #once_cell<#storage_path>,
+                },
+                quote! {
+                    policy,
+                    alloc: alloc.clone(),
+                    units: #once_cell::new(),
+                },
+                (
+                    quote! {
+                        #( #unit_tags )|* => self
+                            .units
+                            .get_or_init(|| Self::__new_storage(self.policy, self.alloc.clone())),
+                    },
+                    quote! { #( #unit_tags )|* => self.units.get(), },
+                ),
+                quote!(matches!(tag, #( #unit_tags )|*)),
+            )
+        };
+        let units_ref = quote! { self.units.get() };
+        let units_mut = quote! { self.units.get_mut() };
+        // The unit storage only takes part once it is created.
+        let fold_units = |units: &TokenStream, op: TokenStream| {
+            if unit_tags.is_empty() {
+                TokenStream::new()
+            } else {
+                quote! { if let Some(units) = #units { #op } }
+            }
+        };
+        let unit_sum = |op: TokenStream| {
+            if unit_tags.is_empty() {
+                TokenStream::new()
+            } else {
+                quote! { + #units_ref.map_or(0, |units| units.#op()) }
+            }
+        };
+        let unit_len = unit_sum(quote!(len));
+        let unit_capacity = unit_sum(quote!(capacity));
+        let unit_chunk_count = unit_sum(quote!(chunk_count));
+        let unit_remaining = if unit_tags.is_empty() {
+            TokenStream::new()
+        } else {
+            quote! { .min(#units_ref.map_or(usize::MAX, |units| units.remaining_in_chunk())) }
+        };
+        let unit_reserve = fold_units(&units_ref, quote! { units.reserve(additional); });
+        let unit_shrink = fold_units(&units_mut, quote! { units.shrink_to_fit(); });
+        let (unit_iter, unit_iter_mut) = if unit_tags.is_empty() {
+            (TokenStream::new(), TokenStream::new())
+        } else {
+            (
+                quote! { .chain(#units_ref.into_iter().flat_map(|units| units.iter())) },
+                quote! { .chain(#units_mut.into_iter().flat_map(|units| units.iter_mut())) },
+            )
+        };
+
+        let storage_types = stored.iter().map(|_| &storage_path);
+        let storage_inits = stored
+            .iter()
+            .map(|_| quote! { Self::__new_storage(policy, alloc.clone()) });
+        let unit_types = units.iter().map(|_| &path);
+
+        let iter_variant = stored
+            .iter()
+            .map(|(_, variant)| format_ident!("iter_{}", snake_case(&variant.ident)));
+        let iter_mut_variant = stored
+            .iter()
+            .map(|(_, variant)| format_ident!("iter_mut_{}", snake_case(&variant.ident)));
+
+        let (unit_storage_arm, unit_find_arm) = unit_storage_arms;
+
+        // Parallel iteration only expands when the `rayon` feature of the interface is enabled.
+        let parallel_iterator = &env.parallel_iterator;
+        let if_rayon = &env.if_rayon;
+        let par_flatten = &env.par_flatten;
+        let par_flatten_mut = &env.par_flatten_mut;
+        let (unit_slices, unit_slices_mut) = if unit_tags.is_empty() {
+            (TokenStream::new(), TokenStream::new())
+        } else {
+            (
+                quote! { .chain(#units_ref.into_iter().flat_map(|units| units.as_slices())) },
+                quote! { .chain(#units_mut.into_iter().flat_map(|units| units.as_mut_slices())) },
+            )
+        };
+        let par_iter_variant = stored
+            .iter()
+            .map(|(_, variant)| format_ident!("par_iter_{}", snake_case(&variant.ident)));
+        let par_iter_mut_variant = stored
+            .iter()
+            .map(|(_, variant)| format_ident!("par_iter_mut_{}", snake_case(&variant.ident)));
+        let parallel_items = quote! {
+            #if_rayon! {
+                impl<#arena_generics_param> #arena_path #where_clause {
+                    /// Iterate over the stored elements in parallel, see `iter`.
+                    pub fn par_iter(&self) -> impl #parallel_iterator<Item = &#path> + '_
+                    where
+                        #path: Sync,
+                    {
+                        #par_flatten(::core::iter::empty() #( .chain(#storages.as_slices()) )* #unit_slices)
+                    }
+
+                    pub fn par_iter_mut(&mut self) -> impl #parallel_iterator<Item = &mut #path> + '_
+                    where
+                        #path: Send,
+                    {
+                        #par_flatten_mut(::core::iter::empty() #( .chain(#storages.as_mut_slices()) )* #unit_slices_mut)
+                    }
+
+                    #(
+                        pub fn #par_iter_variant(&self) -> impl #parallel_iterator<Item = &#path> + '_
+                        where
+                            #path: Sync,
+                        {
+                            #storages.par_iter()
+                        }
+
+                        pub fn #par_iter_mut_variant(&mut self) -> impl #parallel_iterator<Item = &mut #path> + '_
+                        where
+                            #path: Send,
+                        {
+                            #storages.par_iter_mut()
+                        }
+                    )*
+                }
+            }
+        };
+
+        Ok(quote! {
+            impl<#generics_param> #tagged_enum for #path #where_clause {
+                const VARIANTS: u32 = #variant_count;
+
+                type Word = #word;
+            }
+
+            #vis type #idx_id #alias_generics = #tagged_path;
+
+            #vis struct #arena_id #generics #where_clause {
+                storages: ( #( #storage_types, )* ),
+                unit_values: ( #( #unit_types, )* ),
+                #unit_fields_decl
+            }
+
+            impl<#generics_param> #global_arena_path #where_clause {
+                pub fn new(capacity: usize) -> Self {
+                    Self::new_in(capacity, #global)
+                }
+
+                pub fn with_policy(policy: #chunk_policy) -> Self {
+                    Self::with_policy_in(policy, #global)
+                }
+            }
+
+            impl<#arena_generics_param> #arena_path #where_clause {
+                pub fn new_in(capacity: usize, alloc: #alloc_id) -> Self {
+                    Self::with_policy_in(#chunk_policy::Fixed(capacity), alloc)
+                }
+
+                pub fn with_policy_in(policy: #chunk_policy, alloc: #alloc_id) -> Self {
+                    #arena_id {
+                        storages: ( #( #storage_inits, )* ),
+                        unit_values: ( #( #id::#unit_idents, )* ),
+                        #unit_fields_init
+                    }
+                }
+
+                /// Iterate over the elements of all variants with fields, variant by variant in
+                /// declaration order, then over the unit variants allocated mutably.
+                pub fn iter(&self) -> impl DoubleEndedIterator<Item = &#path> + '_ {
+                    ::core::iter::empty()
+                        #( .chain(#storages.iter()) )*
+                        #unit_iter
+                }
+
+                pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut #path> + '_ {
+                    ::core::iter::empty()
+                        #( .chain(#storages.iter_mut()) )*
+                        #unit_iter_mut
+                }
+
+                #(
+                    pub fn #iter_variant(&self) -> impl DoubleEndedIterator<Item = &#path> + ExactSizeIterator + '_ {
+                        #storages.iter()
+                    }
+
+                    pub fn #iter_mut_variant(&mut self) -> impl Iterator<Item = &mut #path> + '_ {
+                        #storages.iter_mut()
+                    }
+                )*
+
+                /// Get a ref to the element of `idx`, or `None` if it is out of bounds.
+                /// # Panics
+                /// Panics while a mutable ref to the element of `idx` is alive.
+                pub fn get<#arena_lifetime>(&#arena_lifetime self, idx: #tagged_path) -> Option<#ref_path> {
+                    let tag = idx.tag();
+                    let found = match idx.idx() {
+                        Some(inner) => self
+                            .__find_storage(tag)
+                            .is_some_and(|storage| storage.lookup(inner).is_some()),
+                        None => #is_unit,
+                    };
+
+                    found.then(|| #ref_id { arena: self, idx })
+                }
+
+                fn __new_storage(policy: #chunk_policy, alloc: #alloc_id) -> #storage_path {
+                    let mut storage = #storage::with_policy_in(policy, alloc);
+
+                    storage.set_max_len(Some(<#tagged_path>::MAX_LEN));
+                    storage
+                }
+
+                fn __tag(t: &#path) -> u32 {
+                    match t {
+                        #( #patterns => #variant_tags, )*
+                    }
+                }
+
+                fn __storage(&self, tag: u32) -> &#storage_path {
+                    match tag {
+                        #( #stored_tags => &self.storages.#stored_fields, )*
+                        #unit_storage_arm
+                        _ => unreachable!("no variant has tag {}", tag),
+                    }
+                }
+
+                /// Storage of the variant of `tag`, unless there is none yet.
+                fn __find_storage(&self, tag: u32) -> Option<&#storage_path> {
+                    match tag {
+                        #( #stored_tags => Some(&self.storages.#stored_fields), )*
+                        #unit_find_arm
+                        _ => None,
+                    }
+                }
+
+                /// Storage of a stored element and its ref there.
+                /// # Safety
+                /// `idx` must be taken from a ref to a stored element of this arena.
+                unsafe fn __stored(&self, idx: #tagged_path) -> (&#storage_path, #storage_ref<#path>) {
+                    let storage = self.__storage(idx.tag());
+                    let inner = idx.idx().expect("enum arena element is not stored");
+
+                    (storage, storage.ref_unchecked(inner))
+                }
+
+                fn __unit_value(&self, tag: u32) -> &#path {
+                    match tag {
+                        #( #unit_tags => &self.unit_values.#unit_fields, )*
+                        _ => unreachable!("no unit variant has tag {}", tag),
+                    }
+                }
+            }
+
+            impl<#arena_generics_param> #index<#tagged_path> for #arena_path #where_clause {
+                type Output = #path;
+
+                fn index(&self, idx: #tagged_path) -> &#path {
+                    let tag = idx.tag();
+
+                    match idx.idx() {
+                        Some(inner) => {
+                            let storage = self.__find_storage(tag).expect("arena index out of bounds");
+                            let r = storage.lookup(inner).expect("arena index out of bounds");
+
+                            unsafe { storage.get(&r) }
+                        }
+                        None if #is_unit => self.__unit_value(tag),
+                        None => panic!("arena index out of bounds"),
+                    }
+                }
+            }
+
+            impl<#arena_generics_param> #arena<#path> for #arena_path #where_clause {
+                type Ref<#arena_lifetime> = #ref_path where #ref_bounds;
+                type MutRef<#arena_lifetime>  = #mut_ref_path where #ref_bounds;
+
+                fn alloc(&self, t: #path) -> Self::Ref<'_> {
+                    let tag = Self::__tag(&t);
+                    let inner = if #is_unit {
+                        None
+                    } else {
+                        Some(self.__storage(tag).alloc(t).to_idx())
+                    };
+
+                    #ref_id {
+                        arena: self,
+                        idx: #tagged_idx::new(tag, inner),
+                    }
+                }
+
+                fn alloc_mut(&self, t: #path) -> Self::MutRef<'_> {
+                    let tag = Self::__tag(&t);
+                    let inner = self.__storage(tag).alloc_mut(t).to_idx();
+
+                    #mut_ref_id {
+                        arena: self,
+                        idx: #tagged_idx::new(tag, Some(inner)),
+                    }
+                }
+
+                fn try_alloc(&self, t: #path) -> #arena_result<Self::Ref<'_>> {
+                    let tag = Self::__tag(&t);
+                    let inner = if #is_unit {
+                        None
+                    } else {
+                        Some(self.__storage(tag).try_alloc(t)?.to_idx())
+                    };
+
+                    Ok(#ref_id {
+                        arena: self,
+                        idx: #tagged_idx::new(tag, inner),
+                    })
+                }
+
+                fn try_alloc_mut(&self, t: #path) -> #arena_result<Self::MutRef<'_>> {
+                    let tag = Self::__tag(&t);
+                    let inner = self.__storage(tag).try_alloc_mut(t)?.to_idx();
+
+                    Ok(#mut_ref_id {
+                        arena: self,
+                        idx: #tagged_idx::new(tag, Some(inner)),
+                    })
+                }
+
+                fn copy<#arena_lifetime>(&#arena_lifetime self, r: &Self::Ref<#arena_lifetime>) -> Self::MutRef<#arena_lifetime>
+                where
+                    #path: Clone,
+                {
+                    self.alloc_mut((**r).clone())
+                }
+
+                fn copy_mut<#arena_lifetime>(
+                    &#arena_lifetime self,
+                    r: &Self::MutRef<#arena_lifetime>,
+                ) -> Self::MutRef<#arena_lifetime>
+                where
+                    #path: Clone,
+                {
+                    self.alloc_mut((**r).clone())
+                }
+
+                /// The number of stored elements. Unit variants allocated immutably take no
+                /// storage and are not counted.
+                fn len(&self) -> usize {
+                    0 #( + #storages.len() )* #unit_len
+                }
+
+                fn capacity(&self) -> usize {
+                    0 #( + #storages.capacity() )* #unit_capacity
+                }
+
+                fn chunk_count(&self) -> usize {
+                    0 #( + #storages.chunk_count() )* #unit_chunk_count
+                }
+
+                /// Every variant is stored apart, so this is the least room left in any storage.
+                fn remaining_in_chunk(&self) -> usize {
+                    usize::MAX #( .min(#storages.remaining_in_chunk()) )* #unit_remaining
+                }
+
+                fn reserve(&self, additional: usize) {
+                    #( #storages.reserve(additional); )*
+                    #unit_reserve
+                }
+
+                fn shrink_to_fit(&mut self) {
+                    #( #storages.shrink_to_fit(); )*
+                    #unit_shrink
+                }
+
+                fn is_empty(&self) -> bool {
+                    self.len() == 0
+                }
+            }
+
+            #parallel_items
+        })
+    }
+}
@@ -0,0 +1,186 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::err::GenerateResult;
+use crate::gen::struct_ref::StructRefGenerator;
+use crate::gen::{CodeGenerator, Env};
+use crate::meta::DataMetaInfo;
+
+pub struct EnumRefGenerator;
+
+unsafe impl Sync for EnumRefGenerator {}
+
+impl CodeGenerator for EnumRefGenerator {
+    fn gen(&self, meta: &DataMetaInfo, env: &Env) -> GenerateResult<TokenStream> {
+        if meta.is_enum {
+            Self::enum_ref(meta, env)
+        } else {
+            Ok(TokenStream::new())
+        }
+    }
+
+    fn create() -> Box<dyn CodeGenerator>
+    where
+        Self: Sized,
+    {
+        Box::new(EnumRefGenerator)
+    }
+}
+
+impl EnumRefGenerator {
+    fn enum_ref(meta: &DataMetaInfo, env: &Env) -> GenerateResult<TokenStream> {
+        let vis = &meta.vis;
+        let id = &meta.name;
+        let ref_id = &meta.ref_id;
+        let mut_ref_id = &meta.mut_ref_id;
+        let arena_id = &meta.arena_id;
+        let arena_lifetime = StructRefGenerator::arena_lifetime();
+        let alloc_param = env.alloc_param(meta, false);
+        let generics = meta.generics_token_steam(
+            Some(StructRefGenerator::arena_lifetime()),
+            env.alloc_param(meta, true),
+        );
+        let generics_param = meta.generics_param_token_steam(
+            Some(StructRefGenerator::arena_lifetime()),
+            alloc_param.clone(),
+        );
+        let where_clause = &meta.generics.where_clause;
+        let generic_args = meta.generic_args_token_stream(None, None);
+        let arena_generic_args = meta.generic_args_token_stream(None, alloc_param.clone());
+        let ref_generic_args =
+            meta.generic_args_token_stream(Some(StructRefGenerator::arena_lifetime()), alloc_param);
+
+        let deref = &env.deref;
+        let deref_mut = &env.deref_mut;
+        let arena_ref = &env.arena_ref;
+        let arena_immut_ref = &env.arena_immut_ref;
+        let arena_mut_ref = &env.arena_mut_ref;
+        let tagged_idx = &env.tagged_idx;
+
+        let arena_path = quote! {#arena_id #arena_generic_args};
+        let path = quote! { #id #generic_args };
+        let ref_path = quote! { #ref_id #ref_generic_args };
+        let mut_ref_path = quote! { #mut_ref_id #ref_generic_args };
+
+        Ok(quote! {
+            #vis struct #ref_id #generics {
+                arena: &#arena_lifetime #arena_path,
+                idx: #tagged_idx<#path>,
+            }
+
+            #vis struct #mut_ref_id #generics {
+                arena: &#arena_lifetime #arena_path,
+                idx: #tagged_idx<#path>,
+            }
+
+            impl<#generics_param> #ref_path #where_clause {
+                pub fn to_idx(&self) -> #tagged_idx<#path> {
+                    self.idx
+                }
+            }
+
+            impl<#generics_param> #mut_ref_path #where_clause {
+                pub fn to_idx(&self) -> #tagged_idx<#path> {
+                    self.idx
+                }
+            }
+
+            impl<#generics_param> Drop for #mut_ref_path #where_clause {
+                fn drop(&mut self) {
+                    unsafe {
+                        let (storage, inner) = self.arena.__stored(self.idx);
+
+                        storage.release_mut(&inner)
+                    }
+                }
+            }
+
+            impl<#generics_param> #deref for #ref_path #where_clause {
+                type Target = #path;
+
+                fn deref(&self) -> &Self::Target {
+                    if self.idx.idx().is_some() {
+                        unsafe {
+                            let (storage, inner) = self.arena.__stored(self.idx);
+
+                            storage.get(&inner)
+                        }
+                    } else {
+                        self.arena.__unit_value(self.idx.tag())
+                    }
+                }
+            }
+
+            impl<#generics_param> #deref for #mut_ref_path #where_clause {
+                type Target = #path;
+
+                fn deref(&self) -> &Self::Target {
+                    unsafe {
+                        let (storage, inner) = self.arena.__stored(self.idx);
+
+                        storage.get(&inner)
+                    }
+                }
+            }
+
+            impl<#generics_param> #deref_mut for #mut_ref_path #where_clause {
+                fn deref_mut(&mut self) -> &mut Self::Target {
+                    unsafe {
+                        let (storage, inner) = self.arena.__stored(self.idx);
+
+                        storage.get_mut(&inner)
+                    }
+                }
+            }
+
+            impl<#generics_param> #arena_ref<#arena_lifetime, #path> for #ref_path #where_clause {
+                type In = #arena_path;
+
+                fn make_mut(&self) -> #mut_ref_path
+                where
+                    #path: Clone, { self.arena.copy(self) }
+
+                fn thaw(self) -> Result<#mut_ref_path, Self> {
+                    // The value of a unit variant is shared by all its refs.
+                    if self.idx.idx().is_none() {
+                        return Err(self);
+                    }
+
+                    let (storage, inner) = unsafe { self.arena.__stored(self.idx) };
+
+                    if unsafe { storage.thaw(&inner) } {
+                        Ok(#mut_ref_id {
+                            arena: self.arena,
+                            idx: self.idx,
+                        })
+                    } else {
+                        Err(self)
+                    }
+                }
+            }
+
+            impl<#generics_param> #arena_ref<#arena_lifetime, #path> for #mut_ref_path #where_clause {
+                type In = #arena_path;
+
+                fn make_mut(&self) -> #mut_ref_path
+                where
+                    #path: Clone, { self.arena.copy_mut(self) }
+
+                fn thaw(self) -> Result<#mut_ref_path, Self> {
+                    Ok(self)
+                }
+            }
+
+            impl<#generics_param> #arena_immut_ref<#arena_lifetime, #path> for #ref_path #where_clause {}
+
+            impl<#generics_param> #arena_mut_ref<#arena_lifetime, #path> for #mut_ref_path #where_clause {
+                fn freeze(self) -> #ref_path {
+                    #ref_id {
+                        arena: self.arena,
+                        idx: self.idx,
+                    }
+                }
+            }
+        })
+    }
+}
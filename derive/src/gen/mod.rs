@@ -1,3 +1,5 @@
+mod enum_arena;
+mod enum_ref;
 mod struct_arena;
 mod struct_ref;
 
@@ -5,8 +7,8 @@ use crate::err::GenerateResult;
 use crate::meta::DataMetaInfo;
 use lazy_static::lazy_static;
 use proc_macro2::TokenStream;
-use quote::quote;
-use syn::{parse_quote, Path};
+use quote::{format_ident, quote};
+use syn::{parse_quote, Ident, Path, TypeParam};
 
 pub type CodeGeneratorBox = Box<dyn CodeGenerator>;
 
@@ -34,34 +36,132 @@ pub trait CodeGenerator: Sync {
 }
 
 pub struct Env {
-    pub interface_path: Path,
     pub deref: Path,
     pub deref_mut: Path,
+    pub manually_drop: Path,
+    pub once_cell: Path,
+    pub index: Path,
+    pub index_mut: Path,
     pub arena_ref: Path,
     pub arena_immut_ref: Path,
     pub arena_mut_ref: Path,
     pub arena: Path,
     pub unsafe_arena: Path,
+    pub unsafe_arena_ref: Path,
+    pub unsafe_arena_into_iter: Path,
+    pub arena_slice_ref: Path,
+    pub arena_mut_slice_ref: Path,
+    pub checkpoint: Path,
+    pub scope: Path,
+    pub scoped_arena: Path,
+    pub arena_result: Path,
+    pub unsafe_sync_arena: Path,
+    pub unsafe_sync_arena_ref: Path,
+    pub idx: Path,
+    pub tagged_idx: Path,
+    pub tagged_enum: Path,
+    pub non_zero_u32: Path,
+    pub non_zero_u64: Path,
+    pub frozen_arena: Path,
+    pub chunk_policy: Path,
+    pub parallel_iterator: Path,
+    pub par_flatten: Path,
+    pub par_flatten_mut: Path,
+    pub if_rayon: Path,
+    pub if_std: Path,
+    pub allocator: Path,
+    pub global: Path,
 }
 
 impl Env {
     pub fn create(interface_path: Path) -> Self {
         Env {
-            interface_path: interface_path.clone(),
-            deref: parse_quote!(std::ops::Deref),
-            deref_mut: parse_quote!(std::ops::DerefMut),
+            deref: parse_quote!(::core::ops::Deref),
+            deref_mut: parse_quote!(::core::ops::DerefMut),
+            manually_drop: parse_quote!(::core::mem::ManuallyDrop),
+            once_cell: parse_quote!(::core::cell::OnceCell),
+            index: parse_quote!(::core::ops::Index),
+            index_mut: parse_quote!(::core::ops::IndexMut),
             arena_ref: parse_quote!(#interface_path::ArenaRef),
             arena_immut_ref: parse_quote!(#interface_path::ArenaImmutRef),
             arena_mut_ref: parse_quote!(#interface_path::ArenaMutRef),
             arena: parse_quote!(#interface_path::Arena),
             unsafe_arena: parse_quote!(#interface_path::UnsafeArena),
+            unsafe_arena_ref: parse_quote!(#interface_path::UnsafeArenaRef),
+            unsafe_arena_into_iter: parse_quote!(#interface_path::UnsafeArenaIntoIter),
+            arena_slice_ref: parse_quote!(#interface_path::ArenaSliceRef),
+            arena_mut_slice_ref: parse_quote!(#interface_path::ArenaMutSliceRef),
+            checkpoint: parse_quote!(#interface_path::Checkpoint),
+            scope: parse_quote!(#interface_path::Scope),
+            scoped_arena: parse_quote!(#interface_path::ScopedArena),
+            arena_result: parse_quote!(#interface_path::ArenaResult),
+            unsafe_sync_arena: parse_quote!(#interface_path::UnsafeSyncArena),
+            unsafe_sync_arena_ref: parse_quote!(#interface_path::UnsafeSyncArenaRef),
+            idx: parse_quote!(#interface_path::Idx),
+            tagged_idx: parse_quote!(#interface_path::TaggedIdx),
+            tagged_enum: parse_quote!(#interface_path::TaggedEnum),
+            non_zero_u32: parse_quote!(::core::num::NonZeroU32),
+            non_zero_u64: parse_quote!(::core::num::NonZeroU64),
+            frozen_arena: parse_quote!(#interface_path::FrozenArena),
+            chunk_policy: parse_quote!(#interface_path::ChunkPolicy),
+            parallel_iterator: parse_quote!(#interface_path::rayon::iter::ParallelIterator),
+            par_flatten: parse_quote!(#interface_path::par_flatten),
+            par_flatten_mut: parse_quote!(#interface_path::par_flatten_mut),
+            if_rayon: parse_quote!(#interface_path::__if_rayon),
+            if_std: parse_quote!(#interface_path::__if_std),
+            allocator: parse_quote!(#interface_path::Allocator),
+            global: parse_quote!(#interface_path::Global),
         }
     }
+
+    /// Storage type and its ref type backing the arena of `meta`.
+    pub fn storage(&self, meta: &DataMetaInfo) -> (&Path, &Path) {
+        if meta.concurrent {
+            (&self.unsafe_sync_arena, &self.unsafe_sync_arena_ref)
+        } else {
+            (&self.unsafe_arena, &self.unsafe_arena_ref)
+        }
+    }
+
+    /// Allocator parameter of the arena of `meta`, defaulting to the global allocator when
+    /// `with_default`. Concurrent storage always uses the global allocator.
+    pub fn alloc_param(&self, meta: &DataMetaInfo, with_default: bool) -> Option<TypeParam> {
+        let allocator = &self.allocator;
+        let global = &self.global;
+
+        if meta.concurrent {
+            None
+        } else if with_default {
+            Some(parse_quote!(__A: #allocator + Clone = #global))
+        } else {
+            Some(parse_quote!(__A: #allocator + Clone))
+        }
+    }
+}
+
+/// `ident` in snake case, such as `bin_op` for `BinOp`.
+pub fn snake_case(ident: &Ident) -> Ident {
+    let mut name = String::new();
+
+    for (i, c) in ident.to_string().chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                name.push('_');
+            }
+            name.extend(c.to_lowercase());
+        } else {
+            name.push(c);
+        }
+    }
+
+    format_ident!("{}", name)
 }
 
 lazy_static! {
     pub static ref generators: Vec<CodeGeneratorBox> = vec![
         struct_ref::StructRefGenerator::create(),
-        struct_arena::StructArenaGenerator::create()
+        struct_arena::StructArenaGenerator::create(),
+        enum_ref::EnumRefGenerator::create(),
+        enum_arena::EnumArenaGenerator::create()
     ];
 }
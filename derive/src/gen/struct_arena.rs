@@ -35,36 +35,97 @@ impl StructArenaGenerator {
         let mut_ref_id = &meta.mut_ref_id;
         let arena_id = &meta.arena_id;
         let arena_lifetime = StructRefGenerator::arena_lifetime();
-        let ref_generic_args =
-            meta.generic_args_token_stream(Some(StructRefGenerator::arena_lifetime()));
-        let generic_args = meta.generic_args_token_stream(None);
-        let generics = meta.generics_token_steam(None);
-        let generics_param = meta.generics_param_token_steam(None);
+        let alloc_param = env.alloc_param(meta, false);
+        let alloc_id = alloc_param.as_ref().map(|a| &a.ident);
+        let ref_generic_args = meta.generic_args_token_stream(
+            Some(StructRefGenerator::arena_lifetime()),
+            alloc_param.clone(),
+        );
+        let generic_args = meta.generic_args_token_stream(None, None);
+        let arena_generic_args = meta.generic_args_token_stream(None, alloc_param.clone());
+        let generics = meta.generics_token_steam(None, env.alloc_param(meta, true));
+        let generics_param = meta.generics_param_token_steam(None, None);
+        let arena_generics_param = meta.generics_param_token_steam(None, alloc_param.clone());
 
         let arena = &env.arena;
-        let unsafe_arena = &env.unsafe_arena;
+        let chunk_policy = &env.chunk_policy;
+        let arena_result = &env.arena_result;
+        let (storage, _) = env.storage(meta);
+        // A concurrent arena does not track its mutable refs.
+        let (alloc_mut, try_alloc_mut) = if meta.concurrent {
+            (quote!(alloc), quote!(try_alloc))
+        } else {
+            (quote!(alloc_mut), quote!(try_alloc_mut))
+        };
 
-        let arena_path = quote! {#arena_id #generic_args};
+        // Without the allocator argument, the arena path names the arena on the global allocator.
+        let global_arena_path = quote! {#arena_id #generic_args};
+        let arena_path = quote! {#arena_id #arena_generic_args};
         let path = quote! { #id #generic_args };
         let ref_path = quote! { #ref_id #ref_generic_args };
         let mut_ref_path = quote! { #mut_ref_id #ref_generic_args };
+        let storage_args = match alloc_id {
+            Some(alloc_id) => quote! { #path, #alloc_id },
+            None => path.clone(),
+        };
+        let ref_bounds = match alloc_id {
+            Some(alloc_id) => quote! { #path: #arena_lifetime, #alloc_id: #arena_lifetime },
+            None => quote! { #path: #arena_lifetime },
+        };
+        let (storage_items, alloc_with) = if meta.concurrent {
+            // The concurrent storage cannot claim a slot, so `alloc_with` keeps its default.
+            (TokenStream::new(), TokenStream::new())
+        } else {
+            (
+                Self::storage_items(meta, env),
+                quote! {
+                    #[inline(always)]
+                    fn alloc_with<F: FnOnce() -> #path>(&self, f: F) -> Self::Ref<'_> {
+                        let mut slot = self.alloc_uninit();
+
+                        unsafe {
+                            slot.as_mut_ptr().write(f());
+                            slot.assume_init()
+                        }
+                    }
+                },
+            )
+        };
 
         Ok(quote! {
             #vis struct #arena_id #generics {
-                inner: #unsafe_arena<#path>,
+                inner: #storage<#storage_args>,
             }
 
-            impl<#generics_param> #arena_path {
+            impl<#generics_param> #global_arena_path {
                 pub fn new(capacity: usize) -> Self {
                     #arena_id {
-                        inner: #unsafe_arena::new(capacity),
+                        inner: #storage::new(capacity),
+                    }
+                }
+
+                pub fn with_policy(policy: #chunk_policy) -> Self {
+                    #arena_id {
+                        inner: #storage::with_policy(policy),
                     }
                 }
             }
 
-            impl<#generics_param> #arena<#path> for #arena_path {
-                type Ref<#arena_lifetime> = #ref_path where #path: #arena_lifetime;
-                type MutRef<#arena_lifetime>  = #mut_ref_path where #path: #arena_lifetime;
+            impl<#arena_generics_param> #arena_path {
+                pub fn set_max_len(&mut self, max_len: Option<usize>) {
+                    self.inner.set_max_len(max_len)
+                }
+
+                pub fn set_max_bytes(&mut self, max_bytes: Option<usize>) {
+                    self.inner.set_max_bytes(max_bytes)
+                }
+            }
+
+            #storage_items
+
+            impl<#arena_generics_param> #arena<#path> for #arena_path {
+                type Ref<#arena_lifetime> = #ref_path where #ref_bounds;
+                type MutRef<#arena_lifetime>  = #mut_ref_path where #ref_bounds;
 
                 fn alloc(&self, t: #path) -> Self::Ref<'_> {
                     #ref_id {
@@ -75,11 +136,27 @@ impl StructArenaGenerator {
 
                 fn alloc_mut(&self, t: #path) -> Self::MutRef<'_> {
                     #mut_ref_id {
-                        inner: self.inner.alloc(t),
+                        inner: self.inner.#alloc_mut(t),
                         arena: self,
                     }
                 }
 
+                fn try_alloc(&self, t: #path) -> #arena_result<Self::Ref<'_>> {
+                    Ok(#ref_id {
+                        inner: self.inner.try_alloc(t)?,
+                        arena: self,
+                    })
+                }
+
+                fn try_alloc_mut(&self, t: #path) -> #arena_result<Self::MutRef<'_>> {
+                    Ok(#mut_ref_id {
+                        inner: self.inner.#try_alloc_mut(t)?,
+                        arena: self,
+                    })
+                }
+
+                #alloc_with
+
                 fn copy<#arena_lifetime>(&#arena_lifetime self, r: &Self::Ref<#arena_lifetime>) -> Self::MutRef<#arena_lifetime>
                 where
                     #path: Clone,
@@ -105,10 +182,254 @@ impl StructArenaGenerator {
                     self.inner.capacity()
                 }
 
+                fn chunk_count(&self) -> usize {
+                    self.inner.chunk_count()
+                }
+
+                fn remaining_in_chunk(&self) -> usize {
+                    self.inner.remaining_in_chunk()
+                }
+
+                fn reserve(&self, additional: usize) {
+                    self.inner.reserve(additional)
+                }
+
+                fn shrink_to_fit(&mut self) {
+                    self.inner.shrink_to_fit()
+                }
+
                 fn is_empty(&self) -> bool {
                     self.inner.is_empty()
                 }
             }
         })
     }
+
+    /// Items only the non-concurrent storage supports: resolving `Idx` handles, contiguous runs, iteration,
+    /// resetting, rollback and freezing.
+    fn storage_items(meta: &DataMetaInfo, env: &Env) -> TokenStream {
+        let id = &meta.name;
+        let ref_id = &meta.ref_id;
+        let uninit_ref_id = &meta.uninit_ref_id;
+        let arena_id = &meta.arena_id;
+        let arena_lifetime = StructRefGenerator::arena_lifetime();
+        let alloc_param = env
+            .alloc_param(meta, false)
+            .expect("non-concurrent storage takes an allocator");
+        let alloc_id = alloc_param.ident.clone();
+        let ref_generic_args = meta.generic_args_token_stream(
+            Some(StructRefGenerator::arena_lifetime()),
+            Some(alloc_param.clone()),
+        );
+        let generic_args = meta.generic_args_token_stream(None, None);
+        let arena_generic_args = meta.generic_args_token_stream(None, Some(alloc_param.clone()));
+        let generics_param = meta.generics_param_token_steam(None, Some(alloc_param));
+
+        let index = &env.index;
+        let index_mut = &env.index_mut;
+        let idx = &env.idx;
+        let frozen_arena = &env.frozen_arena;
+        let into_iter = &env.unsafe_arena_into_iter;
+        let checkpoint = &env.checkpoint;
+        let scope = &env.scope;
+        let scoped_arena = &env.scoped_arena;
+        let slice_ref = &env.arena_slice_ref;
+        let mut_slice_ref = &env.arena_mut_slice_ref;
+        let arena_result = &env.arena_result;
+        let chunk_policy = &env.chunk_policy;
+        let (storage, _) = env.storage(meta);
+        let parallel_items = Self::parallel_items(meta, env);
+
+        let arena_path = quote! {#arena_id #arena_generic_args};
+        let path = quote! { #id #generic_args };
+        let ref_path = quote! { #ref_id #ref_generic_args };
+        let uninit_ref_path = quote! { #uninit_ref_id #ref_generic_args };
+
+        quote! {
+            impl<#generics_param> #arena_path {
+                pub fn new_in(capacity: usize, alloc: #alloc_id) -> Self {
+                    #arena_id {
+                        inner: #storage::new_in(capacity, alloc),
+                    }
+                }
+
+                pub fn with_policy_in(policy: #chunk_policy, alloc: #alloc_id) -> Self {
+                    #arena_id {
+                        inner: #storage::with_policy_in(policy, alloc),
+                    }
+                }
+
+                pub fn alloc_idx(&self, t: #path) -> #idx<#path> {
+                    self.inner.alloc(t).to_idx()
+                }
+
+                pub fn alloc_uninit<#arena_lifetime>(&#arena_lifetime self) -> #uninit_ref_path {
+                    #uninit_ref_id {
+                        inner: self.inner.alloc_uninit(),
+                        arena: self,
+                    }
+                }
+
+                pub fn try_alloc_uninit<#arena_lifetime>(&#arena_lifetime self) -> #arena_result<#uninit_ref_path> {
+                    Ok(#uninit_ref_id {
+                        inner: self.inner.try_alloc_uninit()?,
+                        arena: self,
+                    })
+                }
+
+                pub fn alloc_cyclic<#arena_lifetime>(&#arena_lifetime self, f: impl FnOnce(#idx<#path>) -> #path) -> #ref_path {
+                    let slot = self.alloc_uninit();
+                    let idx = slot.to_idx();
+
+                    slot.init(f(idx))
+                }
+
+                pub fn alloc_extend(&self, iter: impl IntoIterator<Item = #path>) -> #slice_ref<'_, #path, #alloc_id> {
+                    unsafe { #slice_ref::from_raw(&self.inner, self.inner.alloc_extend(iter)) }
+                }
+
+                pub fn alloc_extend_mut(&self, iter: impl IntoIterator<Item = #path>) -> #mut_slice_ref<'_, #path, #alloc_id> {
+                    unsafe { #mut_slice_ref::from_raw(&self.inner, self.inner.alloc_extend_mut(iter)) }
+                }
+
+                pub fn try_alloc_extend(&self, iter: impl IntoIterator<Item = #path>) -> #arena_result<#slice_ref<'_, #path, #alloc_id>> {
+                    let inner = self.inner.try_alloc_extend(iter)?;
+
+                    Ok(unsafe { #slice_ref::from_raw(&self.inner, inner) })
+                }
+
+                pub fn try_alloc_extend_mut(&self, iter: impl IntoIterator<Item = #path>) -> #arena_result<#mut_slice_ref<'_, #path, #alloc_id>> {
+                    let inner = self.inner.try_alloc_extend_mut(iter)?;
+
+                    Ok(unsafe { #mut_slice_ref::from_raw(&self.inner, inner) })
+                }
+
+                /// Get a ref to the element of `idx`, or `None` if it is out of bounds or not
+                /// initialized yet.
+                /// # Panics
+                /// Panics while a mutable ref to the element of `idx` is alive.
+                pub fn get<#arena_lifetime>(&#arena_lifetime self, idx: #idx<#path>) -> Option<#ref_path> {
+                    self.inner.lookup(idx).map(|inner| #ref_id { arena: self, inner })
+                }
+
+                pub fn iter(&self) -> impl DoubleEndedIterator<Item = &#path> + ExactSizeIterator + '_ {
+                    self.inner.iter()
+                }
+
+                pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut #path> + '_ {
+                    self.inner.iter_mut()
+                }
+
+                pub fn as_slices(&self) -> impl Iterator<Item = &[#path]> + '_ {
+                    self.inner.as_slices()
+                }
+
+                pub fn as_mut_slices(&mut self) -> impl Iterator<Item = &mut [#path]> + '_ {
+                    self.inner.as_mut_slices()
+                }
+
+                pub fn refs<#arena_lifetime>(&#arena_lifetime self) -> impl DoubleEndedIterator<Item = #ref_path> + ExactSizeIterator + #arena_lifetime {
+                    self.inner.assert_no_mut_refs();
+
+                    self.inner.refs().map(|inner| #ref_id { arena: self, inner })
+                }
+
+                pub fn reset(&mut self) {
+                    self.inner.reset()
+                }
+
+                pub fn checkpoint(&self) -> #checkpoint<#path> {
+                    self.inner.checkpoint()
+                }
+
+                pub fn rollback(&mut self, checkpoint: #checkpoint<#path>) {
+                    self.inner.rollback(checkpoint)
+                }
+
+                pub fn scope<R>(&mut self, f: impl for<'scope> FnOnce(&#scope<'scope, Self>) -> R) -> R {
+                    #scoped_arena::scope(self, f)
+                }
+
+                pub fn freeze_all(self) -> #frozen_arena<#path, #alloc_id> {
+                    #frozen_arena::new(self.inner)
+                }
+            }
+
+            #parallel_items
+
+            impl<#generics_param> IntoIterator for #arena_path {
+                type Item = #path;
+                type IntoIter = #into_iter<#path, #alloc_id>;
+
+                fn into_iter(self) -> Self::IntoIter {
+                    self.inner.into_iter()
+                }
+            }
+
+            impl<#generics_param> #index<#idx<#path>> for #arena_path {
+                type Output = #path;
+
+                fn index(&self, idx: #idx<#path>) -> &#path {
+                    let r = self.inner.lookup(idx).expect("arena index out of bounds");
+
+                    unsafe { self.inner.get(&r) }
+                }
+            }
+
+            impl<#generics_param> #scoped_arena<#path> for #arena_path {
+                fn alloc_idx(&self, t: #path) -> #idx<#path> {
+                    self.inner.alloc(t).to_idx()
+                }
+
+                fn checkpoint(&self) -> #checkpoint<#path> {
+                    self.inner.checkpoint()
+                }
+
+                fn rollback(&mut self, checkpoint: #checkpoint<#path>) {
+                    self.inner.rollback(checkpoint)
+                }
+            }
+
+            impl<#generics_param> #index_mut<#idx<#path>> for #arena_path {
+                fn index_mut(&mut self, idx: #idx<#path>) -> &mut #path {
+                    self.inner.lookup_mut(idx).expect("arena index out of bounds")
+                }
+            }
+        }
+    }
+
+    /// Parallel iteration, which only expands when the `rayon` feature of the interface is enabled.
+    fn parallel_items(meta: &DataMetaInfo, env: &Env) -> TokenStream {
+        let id = &meta.name;
+        let arena_id = &meta.arena_id;
+        let alloc_param = env.alloc_param(meta, false);
+        let generic_args = meta.generic_args_token_stream(None, None);
+        let arena_generic_args = meta.generic_args_token_stream(None, alloc_param.clone());
+        let generics_param = meta.generics_param_token_steam(None, alloc_param);
+        let parallel_iterator = &env.parallel_iterator;
+        let if_rayon = &env.if_rayon;
+
+        let arena_path = quote! {#arena_id #arena_generic_args};
+        let path = quote! { #id #generic_args };
+
+        quote! {
+            #if_rayon! {
+                impl<#generics_param> #arena_path {
+                    pub fn par_iter(&self) -> impl #parallel_iterator<Item = &#path> + '_
+                    where
+                        #path: Sync,
+                    {
+                        self.inner.par_iter()
+                    }
+
+                    pub fn par_iter_mut(&mut self) -> impl #parallel_iterator<Item = &mut #path> + '_
+                    where
+                        #path: Send,
+                    {
+                        self.inner.par_iter_mut()
+                    }
+                }
+            }
+        }
+    }
 }
@@ -37,37 +37,132 @@ impl StructRefGenerator {
         let id = &meta.name;
         let ref_id = &meta.ref_id;
         let mut_ref_id = &meta.mut_ref_id;
+        let uninit_ref_id = &meta.uninit_ref_id;
         let arena_id = &meta.arena_id;
         let arena_lifetime = Self::arena_lifetime();
-        let generics = meta.generics_token_steam(Some(Self::arena_lifetime()));
-        let generics_param = meta.generics_param_token_steam(Some(Self::arena_lifetime()));
+        let alloc_param = env.alloc_param(meta, false);
+        let generics =
+            meta.generics_token_steam(Some(Self::arena_lifetime()), env.alloc_param(meta, true));
+        let generics_param =
+            meta.generics_param_token_steam(Some(Self::arena_lifetime()), alloc_param.clone());
         let where_clause = &meta.generics.where_clause;
-        let generic_args = meta.generic_args_token_stream(None);
-        let ref_generic_args = meta.generic_args_token_stream(Some(Self::arena_lifetime()));
-        let interface = &env.interface_path;
+        let generic_args = meta.generic_args_token_stream(None, None);
+        let arena_generic_args = meta.generic_args_token_stream(None, alloc_param.clone());
+        let ref_generic_args =
+            meta.generic_args_token_stream(Some(Self::arena_lifetime()), alloc_param);
 
         let deref = &env.deref;
         let deref_mut = &env.deref_mut;
         let arena_ref = &env.arena_ref;
         let arena_immut_ref = &env.arena_immut_ref;
         let arena_mut_ref = &env.arena_mut_ref;
+        let idx = &env.idx;
+        let manually_drop = &env.manually_drop;
+        let (_, storage_ref) = env.storage(meta);
 
-        let arena_path = quote! {#arena_id #generic_args};
+        // Only non-concurrent storage tracks which elements were reached by lookup.
+        let thaw = if meta.concurrent {
+            quote! { Err(self) }
+        } else {
+            quote! {
+                if unsafe { self.arena.inner.thaw(&self.inner) } {
+                    let #ref_id { arena, inner } = self;
+
+                    Ok(#mut_ref_id { arena, inner })
+                } else {
+                    Err(self)
+                }
+            }
+        };
+
+        let arena_path = quote! {#arena_id #arena_generic_args};
         let path = quote! { #id #generic_args };
         let ref_path = quote! { #ref_id #ref_generic_args };
         let mut_ref_path = quote! { #mut_ref_id #ref_generic_args };
+        let uninit_ref_path = quote! { #uninit_ref_id #ref_generic_args };
+        // Concurrent storage neither hands out `Idx`, tracks its mutable refs nor claims slots.
+        let tracking_items = if meta.concurrent {
+            TokenStream::new()
+        } else {
+            quote! {
+                impl<#generics_param> #ref_path #where_clause {
+                    pub fn to_idx(&self) -> #idx<#path> {
+                        self.inner.to_idx()
+                    }
+                }
+
+                impl<#generics_param> #mut_ref_path #where_clause {
+                    pub fn to_idx(&self) -> #idx<#path> {
+                        self.inner.to_idx()
+                    }
+                }
+
+                impl<#generics_param> Drop for #mut_ref_path #where_clause {
+                    fn drop(&mut self) {
+                        unsafe { self.arena.inner.release_mut(&self.inner) }
+                    }
+                }
+
+                #vis struct #uninit_ref_id #generics {
+                    arena: &#arena_lifetime #arena_path,
+                    inner: #storage_ref<#path>
+                }
+
+                impl<#generics_param> #uninit_ref_path #where_clause {
+                    pub fn to_idx(&self) -> #idx<#path> {
+                        self.inner.to_idx()
+                    }
+
+                    pub fn init(self, t: #path) -> #ref_path {
+                        let this = #manually_drop::new(self);
+
+                        unsafe {
+                            this.arena.inner.get_raw(&this.inner).write(t);
+                            this.arena.inner.finish_uninit(&this.inner);
+                        }
+                        #ref_id {
+                            arena: this.arena,
+                            inner: this.inner,
+                        }
+                    }
+
+                    #[inline(always)]
+                    pub fn as_mut_ptr(&mut self) -> *mut #path {
+                        unsafe { self.arena.inner.get_raw(&self.inner) }
+                    }
+
+                    pub unsafe fn assume_init(self) -> #ref_path {
+                        let this = #manually_drop::new(self);
+
+                        this.arena.inner.finish_uninit(&this.inner);
+                        #ref_id {
+                            arena: this.arena,
+                            inner: this.inner,
+                        }
+                    }
+                }
+
+                impl<#generics_param> Drop for #uninit_ref_path #where_clause {
+                    fn drop(&mut self) {
+                        unsafe { self.arena.inner.abandon_uninit(&self.inner) }
+                    }
+                }
+            }
+        };
 
         Ok(quote! {
             #vis struct #ref_id #generics {
                 arena: &#arena_lifetime #arena_path,
-                inner: #interface::UnsafeArenaRef<#path>
+                inner: #storage_ref<#path>
             }
 
             #vis struct #mut_ref_id #generics {
                 arena: &#arena_lifetime #arena_path,
-                inner: #interface::UnsafeArenaRef<#path>
+                inner: #storage_ref<#path>
             }
 
+            #tracking_items
+
             impl<#generics_param> #deref for #ref_path #where_clause {
                 type Target = #path;
 
@@ -96,6 +191,10 @@ impl StructRefGenerator {
                 fn make_mut(&self) -> #mut_ref_path
                 where
                     #path: Clone, { self.arena.copy(self) }
+
+                fn thaw(self) -> Result<#mut_ref_path, Self> {
+                    #thaw
+                }
             }
 
             impl<#generics_param> #arena_ref<#arena_lifetime, #path> for #mut_ref_path #where_clause {
@@ -104,15 +203,20 @@ impl StructRefGenerator {
                 fn make_mut(&self) -> #mut_ref_path
                 where
                     #path: Clone, { self.arena.copy_mut(self) }
+
+                fn thaw(self) -> Result<#mut_ref_path, Self> {
+                    Ok(self)
+                }
             }
 
             impl<#generics_param> #arena_immut_ref<#arena_lifetime, #path> for #ref_path #where_clause {}
 
             impl<#generics_param> #arena_mut_ref<#arena_lifetime, #path> for #mut_ref_path #where_clause {
                 fn freeze(self) -> #ref_path {
-                    let #mut_ref_id { arena, inner } = self;
-
-                    #ref_id { arena, inner }
+                    #ref_id {
+                        arena: self.arena,
+                        inner: self.inner,
+                    }
                 }
             }
         })
@@ -11,7 +11,27 @@ mod gen;
 mod meta;
 mod visitor;
 
-#[proc_macro_derive(Arena, attributes(ref_id, mut_ref_id, arena_id))]
+/// Generate an arena of the type, along with its ref types.
+///
+/// The names of the generated types are set by `ref_id`, `mut_ref_id`, `uninit_ref_id` and
+/// `arena_id`, and for enums `idx_id`. `packed = "u64"` widens the `Idx` of an enum.
+///
+/// With `concurrent`, a struct is stored in a `SyncArena` so it can be allocated from several
+/// threads. Such an arena only allocates: it does not support `alloc_idx`, `get` and indexing,
+/// `alloc_uninit`, `alloc_extend`, `iter`, `reset`, `checkpoint`, `rollback`, `scope`,
+/// `freeze_all` or custom allocators. Enums cannot be `concurrent`.
+#[proc_macro_derive(
+    Arena,
+    attributes(
+        ref_id,
+        mut_ref_id,
+        uninit_ref_id,
+        arena_id,
+        idx_id,
+        packed,
+        concurrent
+    )
+)]
 pub fn arena(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let env = Env::create(parse_quote! {
@@ -7,7 +7,8 @@ use syn::visit::Visit;
 use syn::{
     parse_quote, parse_str, AngleBracketedGenericArguments, Attribute, Data, DeriveInput, Expr,
     ExprLit, ExprPath, Fields, GenericArgument, GenericParam, Generics, Ident, Lifetime,
-    LifetimeParam, Lit, Meta, Path, PathArguments, PathSegment, Type, TypePath, Visibility,
+    LifetimeParam, Lit, Meta, Path, PathArguments, PathSegment, Type, TypeParam, TypePath, Variant,
+    Visibility,
 };
 
 use crate::err::{IntoTokenStream, VisitErr, VisitResult};
@@ -24,9 +25,16 @@ pub struct DataMetaInfo {
     pub aliases: HashSet<Type>,
     pub ref_id: Ident,
     pub mut_ref_id: Ident,
+    pub uninit_ref_id: Ident,
     pub arena_id: Ident,
+    pub idx_id: Ident,
+    /// Word that enum refs are packed into, `u32` or `u64`.
+    pub packed: Ident,
+    pub concurrent: bool,
     pub boxed: HashSet<Type>,
     pub constructors: BTreeMap<Ident, (Fields, Option<Expr>)>,
+    /// Variants of an enum in declaration order, with their original fields.
+    pub variants: Vec<Variant>,
 }
 
 impl DataMetaInfo {
@@ -52,11 +60,32 @@ impl DataMetaInfo {
             .unwrap_or_else(|| Ok(format_ident!("{}MutRef", data_name)))
     }
 
+    fn parse_uninit_ref_ident(attrs: &[Attribute], data_name: &Ident) -> VisitResult<Ident> {
+        Self::parse_attr_ident(attrs, "uninit_ref_id")
+            .unwrap_or_else(|| Ok(format_ident!("{}UninitRef", data_name)))
+    }
+
     fn parse_arena_ident(attrs: &[Attribute], data_name: &Ident) -> VisitResult<Ident> {
         Self::parse_attr_ident(attrs, "arena_id")
             .unwrap_or_else(|| Ok(format_ident!("{}Arena", data_name)))
     }
 
+    fn parse_idx_ident(attrs: &[Attribute], data_name: &Ident) -> VisitResult<Ident> {
+        Self::parse_attr_ident(attrs, "idx_id")
+            .unwrap_or_else(|| Ok(format_ident!("{}Idx", data_name)))
+    }
+
+    fn parse_packed(attrs: &[Attribute]) -> VisitResult<Ident> {
+        let packed =
+            Self::parse_attr_ident(attrs, "packed").unwrap_or_else(|| Ok(format_ident!("u32")))?;
+
+        if packed == "u32" || packed == "u64" {
+            Ok(packed)
+        } else {
+            Err(VisitErr::UnsupportedPacked(packed.span()))
+        }
+    }
+
     fn parse_attr_ident(attrs: &[Attribute], name: &str) -> Option<VisitResult<Ident>> {
         match attrs.iter().find_map(|attr| {
             if let Meta::NameValue(value) = &attr.meta {
@@ -82,6 +111,13 @@ impl DataMetaInfo {
         }
     }
 
+    fn parse_flag(attrs: &[Attribute], name: &str) -> bool {
+        attrs.iter().any(|attr| match &attr.meta {
+            Meta::Path(path) => path.is_ident(name),
+            _ => false,
+        })
+    }
+
     fn generic_args(params: &Generics) -> VisitResult<AngleBracketedGenericArguments> {
         let args = params
             .params
@@ -138,8 +174,12 @@ impl DataMetaInfo {
     pub fn from_derive_input(input: &DeriveInput) -> VisitResult<Self> {
         let ref_id = Self::parse_ref_ident(&input.attrs, &input.ident)?;
         let mut_ref_id = Self::parse_mut_ref_ident(&input.attrs, &input.ident)?;
+        let uninit_ref_id = Self::parse_uninit_ref_ident(&input.attrs, &input.ident)?;
         let arena_id = Self::parse_arena_ident(&input.attrs, &input.ident)?;
+        let idx_id = Self::parse_idx_ident(&input.attrs, &input.ident)?;
+        let packed = Self::parse_packed(&input.attrs)?;
         let aliases = Self::parse_aliases(&input.attrs)?;
+        let concurrent = Self::parse_flag(&input.attrs, "concurrent");
         let generic_args = Self::generic_args(&input.generics)?;
 
         let mut result = DataMetaInfo {
@@ -151,15 +191,27 @@ impl DataMetaInfo {
             aliases,
             ref_id,
             mut_ref_id,
+            uninit_ref_id,
             arena_id,
+            idx_id,
+            packed,
+            concurrent,
             boxed: Default::default(),
             constructors: Default::default(),
+            variants: Default::default(),
         };
 
         match &input.data {
             Data::Enum(e) => {
                 let mut visitor = EnumVisitor::new(&mut result);
                 visitor.visit_data_enum(e);
+
+                if concurrent {
+                    return Err(VisitErr::ConcurrentEnum(input.ident.span()));
+                }
+                if e.variants.is_empty() {
+                    return Err(VisitErr::EmptyEnum(input.ident.span()));
+                }
             }
             _ => {
                 result.boxed.insert(Type::Path(TypePath {
@@ -185,27 +237,15 @@ impl DataMetaInfo {
         }
     }
 
-    pub fn generic_args_token_stream(&self, arena_lifetime: Option<Lifetime>) -> TokenStream {
-        if let Some(t) = arena_lifetime {
-            let mut args = self.generic_args.clone();
-            args.args.insert(0, GenericArgument::Lifetime(t));
-            quote! {
-                #args
-            }
-        } else if self.generic_args.args.is_empty() {
-            TokenStream::new()
-        } else {
-            let args = &self.generic_args;
-
-            quote! {
-                #args
-            }
-        }
-    }
+    /// Generics of the data, led by `arena_lifetime` and followed by the allocator parameter `alloc`.
+    fn extended_generics(
+        &self,
+        arena_lifetime: Option<Lifetime>,
+        alloc: Option<TypeParam>,
+    ) -> Generics {
+        let mut generics = self.generics.clone();
 
-    pub fn generics_token_steam(&self, arena_lifetime: Option<Lifetime>) -> TokenStream {
         if let Some(t) = arena_lifetime {
-            let mut generics = self.generics.clone();
             generics.params.insert(
                 0,
                 GenericParam::Lifetime(LifetimeParam {
@@ -215,55 +255,116 @@ impl DataMetaInfo {
                     bounds: Default::default(),
                 }),
             );
+        }
+        if let Some(a) = alloc {
+            generics.params.push(GenericParam::Type(a));
+        }
+
+        generics
+    }
+
+    pub fn generic_args_token_stream(
+        &self,
+        arena_lifetime: Option<Lifetime>,
+        alloc: Option<TypeParam>,
+    ) -> TokenStream {
+        let mut args = self.generic_args.clone();
+
+        if let Some(t) = arena_lifetime {
+            args.args.insert(0, GenericArgument::Lifetime(t));
+        }
+        if let Some(a) = alloc {
+            args.args.push(GenericArgument::Type(Type::Path(TypePath {
+                qself: None,
+                path: Self::single_ident_path(a.ident),
+            })));
+        }
 
+        if args.args.is_empty() {
+            TokenStream::new()
+        } else {
             quote! {
-                #generics
+                #args
             }
-        } else if self.generic_args.args.is_empty() {
+        }
+    }
+
+    pub fn generics_token_steam(
+        &self,
+        arena_lifetime: Option<Lifetime>,
+        alloc: Option<TypeParam>,
+    ) -> TokenStream {
+        let generics = self.extended_generics(arena_lifetime, alloc);
+
+        if generics.params.is_empty() {
             TokenStream::new()
         } else {
-            let generics = &self.generics;
-
             quote! {
                 #generics
             }
         }
     }
 
-    pub fn generics_param_token_steam(&self, arena_lifetime: Option<Lifetime>) -> TokenStream {
-        if let Some(t) = arena_lifetime {
-            let mut params = self.generics.params.clone();
-            params.insert(
-                0,
-                GenericParam::Lifetime(LifetimeParam {
-                    attrs: vec![],
-                    lifetime: t,
-                    colon_token: None,
-                    bounds: Default::default(),
-                }),
-            );
+    /// Generics of the data without their bounds, which a type alias does not take.
+    pub fn alias_generics_token_stream(&self) -> TokenStream {
+        let mut generics = self.generics.clone();
 
-            quote! {
-                #params
+        for param in &mut generics.params {
+            match param {
+                GenericParam::Lifetime(l) => {
+                    l.colon_token = None;
+                    l.bounds.clear();
+                }
+                GenericParam::Type(ty) => {
+                    ty.colon_token = None;
+                    ty.bounds.clear();
+                }
+                GenericParam::Const(_) => {}
             }
-        } else if self.generic_args.args.is_empty() {
+        }
+
+        if generics.params.is_empty() {
             TokenStream::new()
         } else {
-            let params = &self.generics;
-
             quote! {
-                #params
+                #generics
             }
         }
     }
+
+    pub fn generics_param_token_steam(
+        &self,
+        arena_lifetime: Option<Lifetime>,
+        alloc: Option<TypeParam>,
+    ) -> TokenStream {
+        let params = self.extended_generics(arena_lifetime, alloc).params;
+
+        quote! {
+            #params
+        }
+    }
 }
 
 impl IntoTokenStream for DataMetaInfo {
     fn into_token_stream(self, env: &Env) -> TokenStream {
+        let if_std = &env.if_std;
         let res = generators
             .iter()
             .try_fold(TokenStream::new(), |prev, generator| {
                 generator.gen_onto(&self, env, prev)
+            })
+            // The storage of a concurrent arena only exists with `std`.
+            .map(|tokens| {
+                if self.concurrent {
+                    quote! {
+                        #if_std! {
+                            "a `#[concurrent]` arena";
+                            #tokens
+                        }
+                    }
+                } else {
+                    tokens
+                }
             });
 
         res.into_token_stream(env)
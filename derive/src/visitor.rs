@@ -33,6 +33,7 @@ impl<'meta, 'ast> Visit<'ast> for EnumVisitor<'meta> {
                 node.ident.clone(),
                 (fields, node.discriminant.as_ref().cloned().map(|d| d.1)),
             );
+            self.meta.variants.push(node.clone());
 
             Ok(())
         };
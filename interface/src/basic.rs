@@ -1,19 +1,40 @@
-use std::ops::{Deref, DerefMut};
+use core::{
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut, Index, IndexMut},
+};
 
+use allocator_api2::alloc::{Allocator, Global};
+
+use crate::error::ArenaResult;
+use crate::frozen::FrozenArena;
+use crate::idx::Idx;
 use crate::internal::*;
+use crate::policy::ChunkPolicy;
+use crate::scope::{Scope, ScopedArena};
+use crate::slice::*;
 use crate::traits::*;
 
-pub struct BasicArena<T> {
-    inner: UnsafeArena<T>,
+/// Arena whose chunks are allocated by `A`.
+pub struct BasicArena<T, A: Allocator + Clone = Global> {
+    inner: UnsafeArena<T, A>,
 }
 
-pub struct BasicArenaRef<'arena, T> {
-    arena: &'arena BasicArena<T>,
+pub struct BasicArenaRef<'arena, T, A: Allocator + Clone = Global> {
+    arena: &'arena BasicArena<T, A>,
     inner: UnsafeArenaRef<T>,
 }
 
-pub struct BasicArenaMutRef<'arena, T> {
-    arena: &'arena BasicArena<T>,
+pub struct BasicArenaMutRef<'arena, T, A: Allocator + Clone = Global> {
+    arena: &'arena BasicArena<T, A>,
+    inner: UnsafeArenaRef<T>,
+}
+
+/// Slot claimed by `alloc_uninit`, which becomes an element once initialized by `init`.
+/// Until then, the arena cannot be looked up or iterated, so its `Idx` can already be stored in
+/// other elements without being resolved. If it is dropped instead, the slot stays vacant and its
+/// `Idx` is not found, unless it was the last slot.
+pub struct BasicArenaUninitRef<'arena, T, A: Allocator + Clone = Global> {
+    arena: &'arena BasicArena<T, A>,
     inner: UnsafeArenaRef<T>,
 }
 
@@ -23,27 +44,242 @@ impl<T> BasicArena<T> {
             inner: UnsafeArena::new(capacity),
         }
     }
+
+    pub fn with_policy(policy: ChunkPolicy) -> Self {
+        BasicArena {
+            inner: UnsafeArena::with_policy(policy),
+        }
+    }
 }
 
-impl<T> Arena<T> for BasicArena<T> {
-    type Ref<'arena> = BasicArenaRef<'arena, T> where T: 'arena;
-    type MutRef<'arena>  = BasicArenaMutRef<'arena, T> where T: 'arena;
+impl<T, A: Allocator + Clone> BasicArena<T, A> {
+    pub fn new_in(capacity: usize, alloc: A) -> Self {
+        BasicArena {
+            inner: UnsafeArena::new_in(capacity, alloc),
+        }
+    }
+
+    pub fn with_policy_in(policy: ChunkPolicy, alloc: A) -> Self {
+        BasicArena {
+            inner: UnsafeArena::with_policy_in(policy, alloc),
+        }
+    }
+
+    /// Limit the number of elements, or lift the limit with `None`.
+    /// Allocating past the limit panics, or fails with `try_alloc`.
+    pub fn set_max_len(&mut self, max_len: Option<usize>) {
+        self.inner.set_max_len(max_len)
+    }
+
+    /// Limit the bytes taken by chunks started from now on, or lift the limit with `None`.
+    pub fn set_max_bytes(&mut self, max_bytes: Option<usize>) {
+        self.inner.set_max_bytes(max_bytes)
+    }
+
+    pub fn alloc_idx(&self, t: T) -> Idx<T> {
+        self.inner.alloc(t).to_idx()
+    }
+
+    /// Claim the slot of a new element, to be written in place.
+    /// Elements allocated before it is initialized come after it. Until then, its `Idx` is not
+    /// found by `get` and it is skipped by iteration, while the other elements stay reachable.
+    pub fn alloc_uninit(&self) -> BasicArenaUninitRef<'_, T, A> {
+        BasicArenaUninitRef {
+            inner: self.inner.alloc_uninit(),
+            arena: self,
+        }
+    }
+
+    pub fn try_alloc_uninit(&self) -> ArenaResult<BasicArenaUninitRef<'_, T, A>> {
+        Ok(BasicArenaUninitRef {
+            inner: self.inner.try_alloc_uninit()?,
+            arena: self,
+        })
+    }
+
+    /// Allocate the element returned by `f`, which gets the `Idx` of the element to refer to it.
+    pub fn alloc_cyclic(&self, f: impl FnOnce(Idx<T>) -> T) -> BasicArenaRef<'_, T, A> {
+        let slot = self.alloc_uninit();
+        let idx = slot.to_idx();
+
+        slot.init(f(idx))
+    }
+
+    /// Allocate all items of `iter` contiguously, in one chunk.
+    pub fn alloc_extend(&self, iter: impl IntoIterator<Item = T>) -> ArenaSliceRef<'_, T, A> {
+        unsafe { ArenaSliceRef::from_raw(&self.inner, self.inner.alloc_extend(iter)) }
+    }
+
+    pub fn alloc_extend_mut(
+        &self,
+        iter: impl IntoIterator<Item = T>,
+    ) -> ArenaMutSliceRef<'_, T, A> {
+        unsafe { ArenaMutSliceRef::from_raw(&self.inner, self.inner.alloc_extend_mut(iter)) }
+    }
+
+    pub fn try_alloc_extend(
+        &self,
+        iter: impl IntoIterator<Item = T>,
+    ) -> ArenaResult<ArenaSliceRef<'_, T, A>> {
+        let inner = self.inner.try_alloc_extend(iter)?;
+
+        Ok(unsafe { ArenaSliceRef::from_raw(&self.inner, inner) })
+    }
 
-    fn alloc(&self, t: T) -> BasicArenaRef<'_, T> {
+    pub fn try_alloc_extend_mut(
+        &self,
+        iter: impl IntoIterator<Item = T>,
+    ) -> ArenaResult<ArenaMutSliceRef<'_, T, A>> {
+        let inner = self.inner.try_alloc_extend_mut(iter)?;
+
+        Ok(unsafe { ArenaMutSliceRef::from_raw(&self.inner, inner) })
+    }
+
+    /// Get a ref to the element of `idx`, or `None` if it is out of bounds or not initialized yet.
+    /// # Panics
+    /// Panics while a mutable ref to the element of `idx` is alive. A mutable ref that is
+    /// forgotten instead of dropped keeps its element out of reach until `reset` or `rollback`.
+    pub fn get(&self, idx: Idx<T>) -> Option<BasicArenaRef<'_, T, A>> {
+        self.inner
+            .lookup(idx)
+            .map(|inner| BasicArenaRef { arena: self, inner })
+    }
+
+    /// Iterate over all elements, in allocation order.
+    /// # Panics
+    /// Panics while any mutable ref of this arena is alive.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> + ExactSizeIterator + '_ {
+        self.inner.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> + '_ {
+        self.inner.iter_mut()
+    }
+
+    /// The elements, as one slice per chunk in allocation order, split around vacant slots.
+    /// # Panics
+    /// Panics while any mutable ref of this arena is alive.
+    pub fn as_slices(&self) -> impl Iterator<Item = &[T]> + '_ {
+        self.inner.as_slices()
+    }
+
+    pub fn as_mut_slices(&mut self) -> impl Iterator<Item = &mut [T]> + '_ {
+        self.inner.as_mut_slices()
+    }
+
+    #[cfg(feature = "rayon")]
+    pub fn par_iter(&self) -> impl rayon::iter::ParallelIterator<Item = &T> + '_
+    where
+        T: Sync,
+    {
+        self.inner.par_iter()
+    }
+
+    #[cfg(feature = "rayon")]
+    pub fn par_iter_mut(&mut self) -> impl rayon::iter::ParallelIterator<Item = &mut T> + '_
+    where
+        T: Send,
+    {
+        self.inner.par_iter_mut()
+    }
+
+    /// Refs to all elements, in allocation order.
+    /// # Panics
+    /// Panics while any mutable ref of this arena is alive.
+    pub fn refs(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = BasicArenaRef<'_, T, A>> + ExactSizeIterator + '_ {
+        self.inner.assert_no_mut_refs();
+
+        self.inner
+            .refs()
+            .map(|inner| BasicArenaRef { arena: self, inner })
+    }
+
+    /// Drop all elements, keeping the chunks for later allocations.
+    /// Every `Idx` of the arena becomes invalid.
+    pub fn reset(&mut self) {
+        self.inner.reset()
+    }
+
+    /// Remember the current end of the arena, to drop everything allocated later with `rollback`.
+    pub fn checkpoint(&self) -> Checkpoint<T> {
+        self.inner.checkpoint()
+    }
+
+    /// Drop every element allocated after `checkpoint`.
+    /// Taking `&mut self` ensures no ref into the dropped elements is alive.
+    /// # Panics
+    /// Panics if the arena has already been rolled back or reset past `checkpoint`.
+    pub fn rollback(&mut self, checkpoint: Checkpoint<T>) {
+        self.inner.rollback(checkpoint)
+    }
+
+    /// Run `f` with a `Scope` of this arena, then drop everything allocated in `f`, even if it
+    /// panics. See `ScopedArena::scope`.
+    pub fn scope<R>(&mut self, f: impl for<'scope> FnOnce(&Scope<'scope, Self>) -> R) -> R {
+        ScopedArena::scope(self, f)
+    }
+
+    /// Turn the arena into a read-only `FrozenArena`, keeping every `Idx` valid.
+    pub fn freeze_all(self) -> FrozenArena<T, A> {
+        FrozenArena::new(self.inner)
+    }
+}
+
+impl<T, A: Allocator + Clone> IntoIterator for BasicArena<T, A> {
+    type Item = T;
+    type IntoIter = UnsafeArenaIntoIter<T, A>;
+
+    fn into_iter(self) -> UnsafeArenaIntoIter<T, A> {
+        self.inner.into_iter()
+    }
+}
+
+impl<T, A: Allocator + Clone> Arena<T> for BasicArena<T, A> {
+    type Ref<'arena> = BasicArenaRef<'arena, T, A> where T: 'arena, A: 'arena;
+    type MutRef<'arena>  = BasicArenaMutRef<'arena, T, A> where T: 'arena, A: 'arena;
+
+    fn alloc(&self, t: T) -> BasicArenaRef<'_, T, A> {
         BasicArenaRef {
             inner: self.inner.alloc(t),
             arena: self,
         }
     }
 
-    fn alloc_mut(&self, t: T) -> BasicArenaMutRef<'_, T> {
+    fn alloc_mut(&self, t: T) -> BasicArenaMutRef<'_, T, A> {
         BasicArenaMutRef {
-            inner: self.inner.alloc(t),
+            inner: self.inner.alloc_mut(t),
             arena: self,
         }
     }
 
-    fn copy<'arena>(&'arena self, r: &BasicArenaRef<'arena, T>) -> BasicArenaMutRef<'arena, T>
+    fn try_alloc(&self, t: T) -> ArenaResult<BasicArenaRef<'_, T, A>> {
+        Ok(BasicArenaRef {
+            inner: self.inner.try_alloc(t)?,
+            arena: self,
+        })
+    }
+
+    fn try_alloc_mut(&self, t: T) -> ArenaResult<BasicArenaMutRef<'_, T, A>> {
+        Ok(BasicArenaMutRef {
+            inner: self.inner.try_alloc_mut(t)?,
+            arena: self,
+        })
+    }
+
+    #[inline(always)]
+    fn alloc_with<F: FnOnce() -> T>(&self, f: F) -> BasicArenaRef<'_, T, A> {
+        let mut slot = self.alloc_uninit();
+
+        // Written right where `f` returns, so that it can build the element in the slot.
+        unsafe {
+            slot.as_mut_ptr().write(f());
+            slot.assume_init()
+        }
+    }
+
+    fn copy<'arena>(&'arena self, r: &BasicArenaRef<'arena, T, A>) -> BasicArenaMutRef<'arena, T, A>
     where
         T: Clone,
     {
@@ -52,8 +288,8 @@ impl<T> Arena<T> for BasicArena<T> {
 
     fn copy_mut<'arena>(
         &'arena self,
-        r: &BasicArenaMutRef<'arena, T>,
-    ) -> BasicArenaMutRef<'arena, T>
+        r: &BasicArenaMutRef<'arena, T, A>,
+    ) -> BasicArenaMutRef<'arena, T, A>
     where
         T: Clone,
     {
@@ -71,9 +307,121 @@ impl<T> Arena<T> for BasicArena<T> {
     fn capacity(&self) -> usize {
         self.inner.capacity()
     }
+
+    fn chunk_count(&self) -> usize {
+        self.inner.chunk_count()
+    }
+
+    fn remaining_in_chunk(&self) -> usize {
+        self.inner.remaining_in_chunk()
+    }
+
+    fn reserve(&self, additional: usize) {
+        self.inner.reserve(additional)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.inner.shrink_to_fit()
+    }
+}
+
+/// # Panics
+/// Panics if `idx` is out of bounds or not initialized yet, and like `get` while a mutable ref to
+/// its element is alive.
+impl<T, A: Allocator + Clone> Index<Idx<T>> for BasicArena<T, A> {
+    type Output = T;
+
+    fn index(&self, idx: Idx<T>) -> &T {
+        let r = self.inner.lookup(idx).expect("arena index out of bounds");
+
+        unsafe { self.inner.get(&r) }
+    }
+}
+
+impl<T, A: Allocator + Clone> ScopedArena<T> for BasicArena<T, A> {
+    fn alloc_idx(&self, t: T) -> Idx<T> {
+        self.alloc_idx(t)
+    }
+
+    fn checkpoint(&self) -> Checkpoint<T> {
+        self.checkpoint()
+    }
+
+    fn rollback(&mut self, checkpoint: Checkpoint<T>) {
+        self.rollback(checkpoint)
+    }
+}
+
+impl<T, A: Allocator + Clone> IndexMut<Idx<T>> for BasicArena<T, A> {
+    fn index_mut(&mut self, idx: Idx<T>) -> &mut T {
+        self.inner
+            .lookup_mut(idx)
+            .expect("arena index out of bounds")
+    }
 }
 
-impl<'arena, T> Deref for BasicArenaRef<'arena, T> {
+impl<'arena, T, A: Allocator + Clone> BasicArenaRef<'arena, T, A> {
+    pub fn to_idx(&self) -> Idx<T> {
+        self.inner.to_idx()
+    }
+}
+
+impl<'arena, T, A: Allocator + Clone> BasicArenaMutRef<'arena, T, A> {
+    pub fn to_idx(&self) -> Idx<T> {
+        self.inner.to_idx()
+    }
+}
+
+impl<'arena, T, A: Allocator + Clone> BasicArenaUninitRef<'arena, T, A> {
+    pub fn to_idx(&self) -> Idx<T> {
+        self.inner.to_idx()
+    }
+
+    pub fn init(self, t: T) -> BasicArenaRef<'arena, T, A> {
+        let this = ManuallyDrop::new(self);
+
+        unsafe {
+            this.arena.inner.get_raw(&this.inner).write(t);
+            this.arena.inner.finish_uninit(&this.inner);
+        }
+        BasicArenaRef {
+            arena: this.arena,
+            inner: this.inner,
+        }
+    }
+
+    /// Pointer to the slot, to write the element field by field.
+    #[inline(always)]
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        unsafe { self.arena.inner.get_raw(&self.inner) }
+    }
+
+    /// # Safety
+    /// The element must have been fully written through `as_mut_ptr`.
+    pub unsafe fn assume_init(self) -> BasicArenaRef<'arena, T, A> {
+        let this = ManuallyDrop::new(self);
+
+        this.arena.inner.finish_uninit(&this.inner);
+        BasicArenaRef {
+            arena: this.arena,
+            inner: this.inner,
+        }
+    }
+}
+
+impl<'arena, T, A: Allocator + Clone> Drop for BasicArenaUninitRef<'arena, T, A> {
+    fn drop(&mut self) {
+        unsafe { self.arena.inner.abandon_uninit(&self.inner) }
+    }
+}
+
+impl<'arena, T, A: Allocator + Clone> Drop for BasicArenaMutRef<'arena, T, A> {
+    fn drop(&mut self) {
+        unsafe { self.arena.inner.release_mut(&self.inner) }
+    }
+}
+
+impl<'arena, T, A: Allocator + Clone> Deref for BasicArenaRef<'arena, T, A> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
@@ -81,7 +429,7 @@ impl<'arena, T> Deref for BasicArenaRef<'arena, T> {
     }
 }
 
-impl<'arena, T> Deref for BasicArenaMutRef<'arena, T> {
+impl<'arena, T, A: Allocator + Clone> Deref for BasicArenaMutRef<'arena, T, A> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
@@ -89,40 +437,55 @@ impl<'arena, T> Deref for BasicArenaMutRef<'arena, T> {
     }
 }
 
-impl<'arena, T> DerefMut for BasicArenaMutRef<'arena, T> {
+impl<'arena, T, A: Allocator + Clone> DerefMut for BasicArenaMutRef<'arena, T, A> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         unsafe { self.arena.inner.get_mut(&self.inner) }
     }
 }
 
-impl<'arena, T> ArenaRef<'arena, T> for BasicArenaRef<'arena, T> {
-    type In = BasicArena<T>;
+impl<'arena, T, A: Allocator + Clone> ArenaRef<'arena, T> for BasicArenaRef<'arena, T, A> {
+    type In = BasicArena<T, A>;
 
-    fn make_mut(&self) -> BasicArenaMutRef<'arena, T>
+    fn make_mut(&self) -> BasicArenaMutRef<'arena, T, A>
     where
         T: Clone,
     {
         self.arena.copy(self)
     }
+
+    fn thaw(self) -> Result<BasicArenaMutRef<'arena, T, A>, Self> {
+        if unsafe { self.arena.inner.thaw(&self.inner) } {
+            let BasicArenaRef { arena, inner } = self;
+
+            Ok(BasicArenaMutRef { arena, inner })
+        } else {
+            Err(self)
+        }
+    }
 }
 
-impl<'arena, T> ArenaRef<'arena, T> for BasicArenaMutRef<'arena, T> {
-    type In = BasicArena<T>;
+impl<'arena, T, A: Allocator + Clone> ArenaRef<'arena, T> for BasicArenaMutRef<'arena, T, A> {
+    type In = BasicArena<T, A>;
 
-    fn make_mut(&self) -> BasicArenaMutRef<'arena, T>
+    fn make_mut(&self) -> BasicArenaMutRef<'arena, T, A>
     where
         T: Clone,
     {
         self.arena.copy_mut(self)
     }
-}
 
-impl<'arena, T> ArenaImmutRef<'arena, T> for BasicArenaRef<'arena, T> {}
+    fn thaw(self) -> Result<BasicArenaMutRef<'arena, T, A>, Self> {
+        Ok(self)
+    }
+}
 
-impl<'arena, T> ArenaMutRef<'arena, T> for BasicArenaMutRef<'arena, T> {
-    fn freeze(self) -> BasicArenaRef<'arena, T> {
-        let BasicArenaMutRef { arena, inner } = self;
+impl<'arena, T, A: Allocator + Clone> ArenaImmutRef<'arena, T> for BasicArenaRef<'arena, T, A> {}
 
-        BasicArenaRef { arena, inner }
+impl<'arena, T, A: Allocator + Clone> ArenaMutRef<'arena, T> for BasicArenaMutRef<'arena, T, A> {
+    fn freeze(self) -> BasicArenaRef<'arena, T, A> {
+        BasicArenaRef {
+            arena: self.arena,
+            inner: self.inner,
+        }
     }
 }
@@ -0,0 +1,106 @@
+use core::{
+    marker::PhantomData,
+    ops::{Index, IndexMut},
+};
+
+use crate::basic::BasicArena;
+use crate::idx::Idx;
+use crate::internal::*;
+
+/// Invariant lifetime that tells apart the arenas created by `BasicArena::with`.
+pub(crate) type Brand<'brand> = PhantomData<fn(&'brand ()) -> &'brand ()>;
+
+/// Arena whose handles can only be resolved by itself, checked at compile time.
+pub struct BrandedArena<'brand, T> {
+    inner: UnsafeArena<T>,
+    __brand: Brand<'brand>,
+}
+
+/// Handle of an element in the `BrandedArena` with the same brand.
+pub struct BrandedIdx<'brand, T> {
+    inner: UnsafeArenaRef<T>,
+    __brand: Brand<'brand>,
+}
+
+impl<T> BasicArena<T> {
+    /// Run `f` with a new `BrandedArena`.
+    /// Every call creates its own brand, so mixing handles between arenas fails to compile:
+    ///
+    /// ```compile_fail
+    /// use enum_arena_interface::BasicArena;
+    ///
+    /// BasicArena::with(16, |a| {
+    ///     let idx = a.alloc(1);
+    ///
+    ///     BasicArena::with(16, |b| b[idx])
+    /// });
+    /// ```
+    pub fn with<R>(capacity: usize, f: impl for<'brand> FnOnce(BrandedArena<'brand, T>) -> R) -> R {
+        f(BrandedArena {
+            inner: UnsafeArena::new(capacity),
+            __brand: PhantomData,
+        })
+    }
+}
+
+impl<'brand, T> BrandedArena<'brand, T> {
+    pub fn alloc(&self, t: T) -> BrandedIdx<'brand, T> {
+        BrandedIdx {
+            inner: self.inner.alloc(t),
+            __brand: PhantomData,
+        }
+    }
+
+    pub fn get(&self, idx: BrandedIdx<'brand, T>) -> &T {
+        // The brand guarantees that `idx` was allocated by this arena.
+        unsafe { self.inner.get(&idx.inner) }
+    }
+
+    pub fn get_mut(&mut self, idx: BrandedIdx<'brand, T>) -> &mut T {
+        unsafe { self.inner.get_mut(&idx.inner) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+}
+
+impl<'brand, T> Index<BrandedIdx<'brand, T>> for BrandedArena<'brand, T> {
+    type Output = T;
+
+    fn index(&self, idx: BrandedIdx<'brand, T>) -> &T {
+        self.get(idx)
+    }
+}
+
+impl<'brand, T> IndexMut<BrandedIdx<'brand, T>> for BrandedArena<'brand, T> {
+    fn index_mut(&mut self, idx: BrandedIdx<'brand, T>) -> &mut T {
+        self.get_mut(idx)
+    }
+}
+
+impl<'brand, T> BrandedIdx<'brand, T> {
+    pub fn to_idx(self) -> Idx<T> {
+        self.inner.to_idx()
+    }
+}
+
+impl<'brand, T> Clone for BrandedIdx<'brand, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'brand, T> Copy for BrandedIdx<'brand, T> {}
+
+impl<'brand, T> PartialEq for BrandedIdx<'brand, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_idx() == other.to_idx()
+    }
+}
+
+impl<'brand, T> Eq for BrandedIdx<'brand, T> {}
@@ -0,0 +1,15 @@
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ArenaError {
+    #[error("arena cannot hold more than {0} elements")]
+    TooManyElements(usize),
+    #[error("arena cannot take more than {0} bytes of chunks")]
+    TooManyBytes(usize),
+    #[error("arena index does not fit in u32")]
+    IndexOverflow,
+    #[error("arena failed to allocate a chunk")]
+    AllocFailed,
+}
+
+pub type ArenaResult<T> = Result<T, ArenaError>;
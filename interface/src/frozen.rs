@@ -0,0 +1,82 @@
+use core::ops::{Deref, Index};
+
+use allocator_api2::alloc::{Allocator, Global};
+
+use crate::idx::Idx;
+use crate::internal::*;
+
+/// Arena that can no longer allocate or mutate, so it can be shared between threads.
+pub struct FrozenArena<T, A: Allocator + Clone = Global> {
+    inner: UnsafeArena<T, A>,
+}
+
+pub struct FrozenArenaRef<'arena, T, A: Allocator + Clone = Global> {
+    arena: &'arena FrozenArena<T, A>,
+    inner: UnsafeArenaRef<T>,
+}
+
+// A frozen arena never writes to its storage again. `UnsafeArena` keeps its bookkeeping in
+// `Cell`s, so every method reachable from `&FrozenArena` must only read them: elements are looked
+// up with `lookup_untracked`, never with `lookup`, `refs` or the iterators, which write them.
+unsafe impl<T: Sync, A: Allocator + Clone + Sync> Sync for FrozenArena<T, A> {}
+
+impl<T, A: Allocator + Clone> FrozenArena<T, A> {
+    /// Freeze all elements of `inner`. Refs of `inner` cannot be alive anymore, as it is moved.
+    /// Slots left vacant by `alloc_uninit`, even by a forgotten uninit ref, stay vacant: their
+    /// `Idx` is never found.
+    pub fn new(mut inner: UnsafeArena<T, A>) -> Self {
+        inner.forget_mut_refs();
+
+        FrozenArena { inner }
+    }
+
+    /// Get a ref to the element of `idx`, or `None` if it is out of bounds.
+    pub fn get(&self, idx: Idx<T>) -> Option<FrozenArenaRef<'_, T, A>> {
+        self.inner
+            .lookup_untracked(idx)
+            .map(|inner| FrozenArenaRef { arena: self, inner })
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+}
+
+impl<T, A: Allocator + Clone> Index<Idx<T>> for FrozenArena<T, A> {
+    type Output = T;
+
+    fn index(&self, idx: Idx<T>) -> &T {
+        let r = self
+            .inner
+            .lookup_untracked(idx)
+            .expect("arena index out of bounds");
+
+        unsafe { self.inner.get(&r) }
+    }
+}
+
+impl<'arena, T, A: Allocator + Clone> FrozenArenaRef<'arena, T, A> {
+    pub fn to_idx(&self) -> Idx<T> {
+        self.inner.to_idx()
+    }
+}
+
+impl<'arena, T, A: Allocator + Clone> Clone for FrozenArenaRef<'arena, T, A> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'arena, T, A: Allocator + Clone> Copy for FrozenArenaRef<'arena, T, A> {}
+
+impl<'arena, T, A: Allocator + Clone> Deref for FrozenArenaRef<'arena, T, A> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { self.arena.inner.get(&self.inner) }
+    }
+}
@@ -0,0 +1,213 @@
+use core::{
+    cell::Cell,
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    mem,
+    num::NonZeroU32,
+    ops::{Index, IndexMut},
+};
+
+use crate::idx::Idx;
+use crate::internal::*;
+
+enum Slot<T> {
+    Occupied {
+        generation: NonZeroU32,
+        value: T,
+    },
+    Vacant {
+        generation: NonZeroU32,
+        next_free: Option<Idx<Slot<T>>>,
+    },
+}
+
+/// Arena whose elements can be removed one by one.
+/// Removed slots are reused by later allocations; handles to a removed element are detected
+/// through the generation of their slot and resolve to `None`.
+pub struct GenerationalArena<T> {
+    slots: UnsafeArena<Slot<T>>,
+    free: Cell<Option<Idx<Slot<T>>>>,
+    len: Cell<usize>,
+}
+
+/// Handle of an element in a `GenerationalArena`.
+pub struct GenIdx<T> {
+    index: Idx<T>,
+    generation: NonZeroU32,
+}
+
+impl<T> GenIdx<T> {
+    /// Position of the slot holding the element.
+    pub fn index(self) -> usize {
+        self.index.index()
+    }
+
+    pub fn generation(self) -> u32 {
+        self.generation.get()
+    }
+}
+
+impl<T> GenerationalArena<T> {
+    pub fn new(capacity: usize) -> Self {
+        GenerationalArena {
+            slots: UnsafeArena::new(capacity),
+            free: Cell::new(None),
+            len: Cell::new(0),
+        }
+    }
+
+    pub fn alloc(&self, t: T) -> GenIdx<T> {
+        self.len.set(self.len.get() + 1);
+
+        match self.free.get() {
+            Some(idx) => {
+                let r = self.slots.lookup(idx).unwrap();
+                // A vacant slot is never handed out, so no reference to it can be alive.
+                let slot = unsafe { self.slots.get_mut(&r) };
+                let (generation, next_free) = match slot {
+                    Slot::Vacant {
+                        generation,
+                        next_free,
+                    } => (*generation, *next_free),
+                    Slot::Occupied { .. } => unreachable!(),
+                };
+
+                self.free.set(next_free);
+                *slot = Slot::Occupied {
+                    generation,
+                    value: t,
+                };
+
+                GenIdx {
+                    index: idx.cast(),
+                    generation,
+                }
+            }
+            None => {
+                let generation = NonZeroU32::MIN;
+                let r = self.slots.alloc(Slot::Occupied {
+                    generation,
+                    value: t,
+                });
+
+                GenIdx {
+                    index: r.to_idx().cast(),
+                    generation,
+                }
+            }
+        }
+    }
+
+    /// Get the element of `idx`, or `None` if it has been removed.
+    pub fn get(&self, idx: GenIdx<T>) -> Option<&T> {
+        let r = self.slots.lookup(idx.index.cast())?;
+
+        match unsafe { self.slots.get(&r) } {
+            Slot::Occupied { generation, value } if *generation == idx.generation => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, idx: GenIdx<T>) -> Option<&mut T> {
+        match self.slots.lookup_mut(idx.index.cast())? {
+            Slot::Occupied { generation, value } if *generation == idx.generation => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn contains(&self, idx: GenIdx<T>) -> bool {
+        self.get(idx).is_some()
+    }
+
+    /// Remove the element of `idx` and return it, or `None` if it has been removed already.
+    /// The slot is reused by a later allocation under a new generation.
+    pub fn remove(&mut self, idx: GenIdx<T>) -> Option<T> {
+        let slot = self.slots.lookup_mut(idx.index.cast())?;
+
+        match slot {
+            Slot::Occupied { generation, .. } if *generation == idx.generation => {}
+            _ => return None,
+        }
+
+        // A slot whose generation is exhausted is retired instead of being reused.
+        let next_generation = idx.generation.checked_add(1);
+        let vacant = Slot::Vacant {
+            generation: next_generation.unwrap_or(idx.generation),
+            next_free: self.free.get(),
+        };
+
+        if next_generation.is_some() {
+            self.free.set(Some(idx.index.cast()));
+        }
+        self.len.set(self.len.get() - 1);
+
+        match mem::replace(slot, vacant) {
+            Slot::Occupied { value, .. } => Some(value),
+            Slot::Vacant { .. } => unreachable!(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len.get()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Index<GenIdx<T>> for GenerationalArena<T> {
+    type Output = T;
+
+    fn index(&self, idx: GenIdx<T>) -> &T {
+        self.get(idx).expect("element has been removed")
+    }
+}
+
+impl<T> IndexMut<GenIdx<T>> for GenerationalArena<T> {
+    fn index_mut(&mut self, idx: GenIdx<T>) -> &mut T {
+        self.get_mut(idx).expect("element has been removed")
+    }
+}
+
+impl<T> Clone for GenIdx<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for GenIdx<T> {}
+
+impl<T> PartialEq for GenIdx<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.generation == other.generation
+    }
+}
+
+impl<T> Eq for GenIdx<T> {}
+
+impl<T> PartialOrd for GenIdx<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for GenIdx<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.index, self.generation).cmp(&(other.index, other.generation))
+    }
+}
+
+impl<T> Hash for GenIdx<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+        self.generation.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for GenIdx<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "GenIdx({}v{})", self.index(), self.generation)
+    }
+}
@@ -0,0 +1,76 @@
+use core::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    num::NonZeroU32,
+};
+
+/// Compact handle of an element in an arena.
+/// Unlike arena refs it does not borrow the arena, so it can be stored, copied and sent freely.
+pub struct Idx<T> {
+    index: NonZeroU32,
+    __marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Idx<T> {
+    pub(crate) fn from_raw(index: NonZeroU32) -> Self {
+        Idx {
+            index,
+            __marker: Default::default(),
+        }
+    }
+
+    pub(crate) fn raw(self) -> NonZeroU32 {
+        self.index
+    }
+
+    pub(crate) fn cast<U>(self) -> Idx<U> {
+        Idx::from_raw(self.index)
+    }
+
+    /// Position of the element in allocation order.
+    pub fn index(self) -> usize {
+        self.index.get() as usize - 1
+    }
+}
+
+impl<T> Clone for Idx<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Idx<T> {}
+
+impl<T> PartialEq for Idx<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+    }
+}
+
+impl<T> Eq for Idx<T> {}
+
+impl<T> PartialOrd for Idx<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Idx<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.index.cmp(&other.index)
+    }
+}
+
+impl<T> Hash for Idx<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state)
+    }
+}
+
+impl<T> fmt::Debug for Idx<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Idx({})", self.index())
+    }
+}
@@ -1,30 +1,542 @@
-use std::{cell::UnsafeCell, marker::PhantomData};
+use core::{
+    cell::{Cell, UnsafeCell},
+    iter::{self, Flatten},
+    marker::PhantomData,
+    mem::{self, size_of, ManuallyDrop},
+    num::NonZeroU32,
+    ops::Range,
+    ptr::{self, NonNull},
+    slice,
+};
 
-pub struct UnsafeArena<T> {
-    chunks: UnsafeCell<Vec<Vec<T>>>,
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use alloc::{vec, vec::Vec};
+
+use allocator_api2::{
+    alloc::{Allocator, Global},
+    vec::Vec as AllocVec,
+};
+
+use crate::error::{ArenaError, ArenaResult};
+use crate::idx::Idx;
+use crate::policy::ChunkPolicy;
+
+static NEXT_ARENA_ID: AtomicU32 = AtomicU32::new(0);
+
+/// Buffer of an `UnsafeArena`, which is never reallocated once created.
+/// Elements are only reached through pointers derived from `ptr`, so a reference to one element
+/// stays valid while other elements are written or pushed.
+/// `size` is the number of elements the policy gives to the chunk, which may be less than the
+/// capacity of the buffer.
+struct Chunk<T, A: Allocator> {
+    ptr: NonNull<T>,
+    start: usize,
+    len: usize,
+    size: usize,
     capacity: usize,
+    /// Taken out only when the buffer is handed back to it.
+    alloc: ManuallyDrop<A>,
+    __marker: PhantomData<T>,
+}
+
+/// Chunk buffers are allocated by `A`.
+pub struct UnsafeArena<T, A: Allocator = Global> {
+    chunks: UnsafeCell<Vec<Chunk<T, A>>>,
+    policy: ChunkPolicy,
+    /// Runs of chunks sized exactly by `policy`, whose elements are located without a search.
+    /// `reserve`, `shrink_to_fit` and `max_bytes` may give chunks other sizes.
+    regular: Cell<Regular>,
+    /// Empty buffers kept by `reset`, taken again from the back as new chunks are started.
+    spare: Cell<Vec<Chunk<T, A>>>,
+    alloc: A,
+    max_len: Option<usize>,
+    /// Cap on the bytes of all chunk buffers, counting `size` elements for each chunk.
+    max_bytes: Option<usize>,
+    /// Index ranges of the elements handed out through live mutable refs, one per ref.
+    borrowed: UnsafeCell<Vec<Range<usize>>>,
+    /// Elements below this index may have been reached by lookup or iteration, so more than one
+    /// ref may point to them until the arena is reset or rolled back.
+    shared: Cell<usize>,
+    /// Indices of the slots claimed by `alloc_uninit` that hold no element, in increasing order.
+    /// They are not counted in the length, and are skipped by lookup, iteration and drop. An
+    /// abandoned slot stays vacant until it is reset or rolled back.
+    vacant: UnsafeCell<Vec<usize>>,
+    /// Recorded by checkpoints, and refs with debug assertions or the `checked` feature, to tell
+    /// which arena created them.
+    id: u32,
+    /// Number of resets so far, which make every earlier checkpoint invalid.
+    resets: u32,
+}
+
+/// The chunks of an `UnsafeArena` that are sized exactly by its policy: the ones before the first
+/// chunk sized otherwise, and the ones after the last.
+#[derive(Clone, Copy)]
+struct Regular {
+    /// Elements below this index are in the leading run.
+    prefix_end: usize,
+    /// Elements from this index on are in the trailing run.
+    tail_start: usize,
+    /// Index the policy gives to the first element of the trailing run.
+    tail_shift: usize,
+}
+
+impl Regular {
+    const ALL: Regular = Regular {
+        prefix_end: usize::MAX,
+        tail_start: usize::MAX,
+        tail_shift: 0,
+    };
+
+    /// Find the runs of `chunks`, after some of them were dropped.
+    fn find<T, A: Allocator>(policy: &ChunkPolicy, chunks: &[Chunk<T, A>]) -> Regular {
+        let mut regular = Regular::ALL;
+
+        for pos in 0..chunks.len() {
+            regular.check(policy, chunks, pos);
+        }
+        regular
+    }
+
+    /// Restart the trailing run after the `pos`-th chunk if it is not sized by `policy`.
+    /// The chunks after it must not have been started yet.
+    fn check<T, A: Allocator>(&mut self, policy: &ChunkPolicy, chunks: &[Chunk<T, A>], pos: usize) {
+        let chunk = &chunks[pos];
+
+        if chunk.size == policy.chunk_size(pos) {
+            return;
+        }
+        // Without a closed form for the chunk starts, every element is searched anyway.
+        if let Some(tail_shift) = policy.chunk_start(pos + 1) {
+            self.prefix_end = self.prefix_end.min(chunk.start);
+            self.tail_start = chunk.start + chunk.size;
+            self.tail_shift = tail_shift;
+        }
+    }
 }
 
+/// Items of an `alloc_extend` written after the last element of a chunk, where they are not
+/// reached by lookup, iteration or other allocations. They are dropped with it unless taken.
+struct Pending<T> {
+    ptr: *mut T,
+    len: usize,
+}
+
+impl<T> Drop for Pending<T> {
+    fn drop(&mut self) {
+        unsafe { ptr::drop_in_place(ptr::slice_from_raw_parts_mut(self.ptr, self.len)) };
+    }
+}
+
+/// Position of an element in an `UnsafeArena`, counted over all chunks.
+/// The index is stored shifted by one, so `Option<UnsafeArenaRef<T>>` takes no extra space.
+/// With debug assertions or the `checked` feature, it also records the arena that created it.
 pub struct UnsafeArenaRef<T> {
-    chunk: usize,
-    elem: usize,
+    index: NonZeroU32,
+    #[cfg(any(debug_assertions, feature = "checked"))]
+    arena: u32,
+    __marker: PhantomData<T>,
+}
+
+impl<T> UnsafeArenaRef<T> {
+    pub fn to_idx(&self) -> Idx<T> {
+        Idx::from_raw(self.index)
+    }
+}
+
+/// Run of `len` contiguous elements of an `UnsafeArena`, starting at the `start`-th element.
+/// With debug assertions or the `checked` feature, it also records the arena that created it.
+pub struct UnsafeArenaSliceRef<T> {
+    start: u32,
+    len: u32,
+    #[cfg(any(debug_assertions, feature = "checked"))]
+    arena: u32,
+    __marker: PhantomData<T>,
+}
+
+/// Number of elements of an `UnsafeArena` at some point, which it can be rolled back to.
+/// It also records the arena that created it and how many times that arena was reset, so it is
+/// never used with another arena or after a reset.
+pub struct Checkpoint<T> {
+    len: usize,
+    arena: u32,
+    resets: u32,
     __marker: PhantomData<T>,
 }
 
+impl<T> Clone for Checkpoint<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Checkpoint<T> {}
+
+/// Indices of the elements in `range`, skipping the vacant slots among them.
+struct Occupied {
+    range: Range<usize>,
+    /// Vacant slots in `range` that are not skipped yet.
+    vacant: vec::IntoIter<usize>,
+}
+
+/// Owning iterator over the elements of an `UnsafeArena`, in allocation order.
+pub struct UnsafeArenaIntoIter<T, A: Allocator = Global> {
+    inner: Flatten<vec::IntoIter<AllocVec<T, A>>>,
+}
+
+impl<T> Clone for UnsafeArenaRef<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for UnsafeArenaRef<T> {}
+
+impl<T> Clone for UnsafeArenaSliceRef<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for UnsafeArenaSliceRef<T> {}
+
+impl Iterator for Occupied {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        loop {
+            let index = self.range.next()?;
+
+            if self.vacant.as_slice().first() != Some(&index) {
+                return Some(index);
+            }
+            self.vacant.next();
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.range.len() - self.vacant.len();
+
+        (len, Some(len))
+    }
+}
+
+impl DoubleEndedIterator for Occupied {
+    fn next_back(&mut self) -> Option<usize> {
+        loop {
+            let index = self.range.next_back()?;
+
+            if self.vacant.as_slice().last() != Some(&index) {
+                return Some(index);
+            }
+            self.vacant.next_back();
+        }
+    }
+}
+
+impl ExactSizeIterator for Occupied {}
+
+/// The indices of `vacant` in `range`.
+fn vacant_in(vacant: &[usize], range: Range<usize>) -> &[usize] {
+    let from = vacant.partition_point(|&index| index < range.start);
+    let to = vacant.partition_point(|&index| index < range.end);
+
+    &vacant[from..to]
+}
+
+impl<T, A: Allocator> Chunk<T, A> {
+    fn new(start: usize, size: usize, alloc: A) -> Self {
+        Self::from_vec(start, size, AllocVec::with_capacity_in(size, alloc))
+    }
+
+    fn try_new(start: usize, size: usize, alloc: A) -> ArenaResult<Self> {
+        let mut buf = AllocVec::new_in(alloc);
+
+        buf.try_reserve_exact(size)
+            .map_err(|_| ArenaError::AllocFailed)?;
+
+        Ok(Self::from_vec(start, size, buf))
+    }
+
+    /// Take over the buffer and the elements of `buf`.
+    fn from_vec(start: usize, size: usize, buf: AllocVec<T, A>) -> Self {
+        let (ptr, len, capacity, alloc) = buf.into_raw_parts_with_alloc();
+
+        Chunk {
+            // The pointer of a `Vec` is never null.
+            ptr: unsafe { NonNull::new_unchecked(ptr) },
+            start,
+            len,
+            size,
+            capacity,
+            alloc: ManuallyDrop::new(alloc),
+            __marker: PhantomData,
+        }
+    }
+
+    /// Rebuild the `Vec` of the buffer. The chunk must not be dropped afterwards.
+    unsafe fn take_vec(&mut self) -> AllocVec<T, A> {
+        let alloc = ManuallyDrop::take(&mut self.alloc);
+
+        AllocVec::from_raw_parts_in(self.ptr.as_ptr(), self.len, self.capacity, alloc)
+    }
+
+    /// # Safety
+    /// `elem` must be less than `len`.
+    unsafe fn slot(&self, elem: usize) -> *mut T {
+        self.ptr.as_ptr().add(elem)
+    }
+
+    fn into_vec(self) -> AllocVec<T, A> {
+        let mut chunk = ManuallyDrop::new(self);
+
+        unsafe { chunk.take_vec() }
+    }
+
+    /// Drop the elements from `len` on, keeping the buffer. `vacant` are the indices of the
+    /// arena among them that hold no element, in increasing order.
+    fn truncate(&mut self, len: usize, vacant: &[usize]) {
+        let end = self.len;
+        let mut from = len;
+
+        // Leak the rest rather than drop twice if a destructor panics.
+        self.len = len;
+        for to in vacant.iter().map(|index| index - self.start).chain([end]) {
+            let dropped =
+                ptr::slice_from_raw_parts_mut(unsafe { self.ptr.as_ptr().add(from) }, to - from);
+
+            unsafe { ptr::drop_in_place(dropped) };
+            from = to + 1;
+        }
+    }
+
+    /// Move the elements over the slots of `vacant`, indices of the arena in this chunk in
+    /// increasing order, so that the chunk holds only elements.
+    fn close_vacant(&mut self, vacant: &[usize]) {
+        for (closed, index) in vacant.iter().enumerate() {
+            let elem = index - self.start;
+            let next = vacant
+                .get(closed + 1)
+                .map_or(self.len, |next| next - self.start);
+            let base = self.ptr.as_ptr();
+
+            unsafe { ptr::copy(base.add(elem + 1), base.add(elem - closed), next - elem - 1) };
+        }
+        self.len -= vacant.len();
+    }
+
+    /// Reallocate the buffer to hold exactly `len` elements, which moves them.
+    fn shrink_to_fit(&mut self) {
+        let mut buf = unsafe { self.take_vec() };
+
+        buf.shrink_to_fit();
+        // The old chunk gave up its buffer, so it must not be dropped.
+        unsafe { ptr::write(self, Self::from_vec(self.start, self.len, buf)) };
+    }
+
+    /// # Safety
+    /// `len` must be less than `size`.
+    unsafe fn push(&mut self, t: T) {
+        self.ptr.as_ptr().add(self.len).write(t);
+        self.len += 1;
+    }
+}
+
+impl<T, A: Allocator> Drop for Chunk<T, A> {
+    fn drop(&mut self) {
+        // The elements are dropped by the arena, which knows the vacant slots; any left are leaked.
+        self.len = 0;
+        drop(unsafe { self.take_vec() });
+    }
+}
+
+unsafe impl<T: Send, A: Allocator + Send> Send for Chunk<T, A> {}
+
+impl<T, A: Allocator> Drop for UnsafeArena<T, A> {
+    fn drop(&mut self) {
+        let vacant = self.vacant.get_mut();
+
+        for chunk in self.chunks.get_mut() {
+            chunk.truncate(0, vacant_in(vacant, chunk.start..chunk.start + chunk.len));
+        }
+    }
+}
+
 impl<T> UnsafeArena<T> {
+    /// Create an arena whose chunks hold `capacity` elements each.
+    /// A `capacity` of zero is treated as one.
     pub fn new(capacity: usize) -> Self {
+        Self::new_in(capacity, Global)
+    }
+
+    /// Create an arena whose chunks are sized by `policy`.
+    pub fn with_policy(policy: ChunkPolicy) -> Self {
+        Self::with_policy_in(policy, Global)
+    }
+}
+
+impl<T, A: Allocator + Clone> UnsafeArena<T, A> {
+    /// Create an arena whose chunks hold `capacity` elements each and are allocated by `alloc`.
+    pub fn new_in(capacity: usize, alloc: A) -> Self {
+        Self::with_policy_in(ChunkPolicy::Fixed(capacity), alloc)
+    }
+
+    /// Create an arena whose chunks are sized by `policy` and allocated by `alloc`.
+    pub fn with_policy_in(policy: ChunkPolicy, alloc: A) -> Self {
         UnsafeArena {
-            chunks: UnsafeCell::new(vec![Vec::with_capacity(capacity)]),
-            capacity,
+            chunks: UnsafeCell::new(vec![Chunk::new(0, policy.chunk_size(0), alloc.clone())]),
+            policy,
+            regular: Cell::new(Regular::ALL),
+            spare: Cell::new(Vec::new()),
+            alloc,
+            max_len: None,
+            max_bytes: None,
+            borrowed: UnsafeCell::new(Vec::new()),
+            shared: Cell::new(0),
+            vacant: UnsafeCell::new(Vec::new()),
+            id: NEXT_ARENA_ID.fetch_add(1, Ordering::Relaxed),
+            resets: 0,
+        }
+    }
+
+    /// Find the chunk and the position inside it of the `index`-th element.
+    /// The position may be past the end of the chunk or the chunk may not exist yet.
+    fn locate(&self, chunks: &[Chunk<T, A>], index: usize) -> (usize, usize) {
+        self.locate_directly(index).unwrap_or_else(|| {
+            let chunk = chunks.partition_point(|c| c.start <= index) - 1;
+
+            (chunk, index - chunks[chunk].start)
+        })
+    }
+
+    /// Locate the `index`-th element like `locate` in O(1), if it is in a run of chunks sized by
+    /// the policy.
+    fn locate_directly(&self, index: usize) -> Option<(usize, usize)> {
+        let regular = self.regular.get();
+
+        if index < regular.prefix_end {
+            self.policy.locate(index)
+        } else if index >= regular.tail_start {
+            (index - regular.tail_start)
+                .checked_add(regular.tail_shift)
+                .and_then(|index| self.policy.locate(index))
+        } else {
+            None
         }
     }
 
+    /// Whether the element of `idx` is found in O(1), rather than by searching the chunks.
+    /// Chunks that are not sized by the policy, because of `reserve`, `shrink_to_fit` or
+    /// `max_bytes`, are searched, and so are all chunks of a `Custom` policy.
+    pub fn locates_directly(&self, idx: Idx<T>) -> bool {
+        self.locate_directly(idx.index()).is_some()
+    }
+
+    /// Start a chunk, reusing the next spare buffer if it is large enough.
+    /// The chunk is cut down to the rest of `max_bytes` if needed.
+    fn new_chunk(&self, start: usize, size: usize) -> ArenaResult<Chunk<T, A>> {
+        let size = match self.max_bytes {
+            Some(max_bytes) if size_of::<T>() != 0 => {
+                let used = self.capacity() * size_of::<T>();
+                let available = max_bytes.saturating_sub(used) / size_of::<T>();
+
+                if available == 0 {
+                    return Err(ArenaError::TooManyBytes(max_bytes));
+                }
+                size.min(available)
+            }
+            _ => size,
+        };
+        let mut spare = self.spare.take();
+        let chunk = match spare.pop() {
+            Some(mut chunk) if chunk.capacity >= size => {
+                chunk.start = start;
+                chunk.size = size;
+                Ok(chunk)
+            }
+            _ => Chunk::try_new(start, size, self.alloc.clone()),
+        };
+
+        self.spare.set(spare);
+
+        chunk
+    }
+
+    /// Push `chunk` after the last one, keeping track of whether it is sized by the policy.
+    fn push_chunk(&self, chunk: Chunk<T, A>) {
+        let chunks = unsafe { &mut *(self.chunks.get()) };
+        let mut regular = self.regular.get();
+
+        chunks.push(chunk);
+        regular.check(&self.policy, chunks, chunks.len() - 1);
+        self.regular.set(regular);
+    }
+
+    fn make_ref(&self, index: NonZeroU32) -> UnsafeArenaRef<T> {
+        UnsafeArenaRef {
+            index,
+            #[cfg(any(debug_assertions, feature = "checked"))]
+            arena: self.id,
+            __marker: Default::default(),
+        }
+    }
+
+    /// Rebuild the ref an `Idx` was taken from, without checking it.
+    /// # Safety
+    /// `idx` must be taken from a ref constructed by this arena.
+    pub unsafe fn ref_unchecked(&self, idx: Idx<T>) -> UnsafeArenaRef<T> {
+        self.make_ref(idx.raw())
+    }
+
     /// Get raw pointer over `UnsafeArenaRef`.
     /// # Safety
     /// The calling is safe if `r` is constructed by the same arena.
+    /// With debug assertions or the `checked` feature, a ref of another arena panics instead.
     pub unsafe fn get_raw(&self, r: &UnsafeArenaRef<T>) -> *mut T {
-        let chunks = &mut *(self.chunks.get());
-        chunks.get_unchecked_mut(r.chunk).get_unchecked_mut(r.elem) as *mut T
+        let chunks = &*(self.chunks.get());
+        let (chunk, elem) = self.locate(chunks, r.index.get() as usize - 1);
+
+        #[cfg(any(debug_assertions, feature = "checked"))]
+        {
+            assert_eq!(
+                r.arena, self.id,
+                "arena ref is used with an arena that did not create it"
+            );
+            assert!(
+                chunks.get(chunk).is_some_and(|c| elem < c.len),
+                "arena ref is out of bounds"
+            );
+        }
+
+        chunks.get_unchecked(chunk).slot(elem)
+    }
+
+    /// Get raw pointer over `UnsafeArenaSliceRef`.
+    /// # Safety
+    /// The calling is safe if `r` is constructed by the same arena.
+    /// With debug assertions or the `checked` feature, a ref of another arena panics instead.
+    pub unsafe fn get_slice_raw(&self, r: &UnsafeArenaSliceRef<T>) -> *mut [T] {
+        #[cfg(any(debug_assertions, feature = "checked"))]
+        assert_eq!(
+            r.arena, self.id,
+            "arena ref is used with an arena that did not create it"
+        );
+
+        let len = r.len as usize;
+        if len == 0 {
+            return ptr::slice_from_raw_parts_mut(NonNull::dangling().as_ptr(), 0);
+        }
+
+        let chunks = &*(self.chunks.get());
+        let (chunk, elem) = self.locate(chunks, r.start as usize);
+
+        #[cfg(any(debug_assertions, feature = "checked"))]
+        assert!(
+            chunks.get(chunk).is_some_and(|c| elem + len <= c.len),
+            "arena ref is out of bounds"
+        );
+
+        ptr::slice_from_raw_parts_mut(chunks.get_unchecked(chunk).slot(elem), len)
     }
 
     /// Get primitive reference over `UnsafeArenaRef`.
@@ -41,44 +553,712 @@ impl<T> UnsafeArena<T> {
         &mut *self.get_raw(r)
     }
 
+    /// # Panics
+    /// Panics if the allocation fails, see `try_alloc`.
     pub fn alloc(&self, t: T) -> UnsafeArenaRef<T> {
+        self.try_alloc(t).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Allocate an element, or fail if the limits of the arena are reached,
+    /// the index would not fit in `u32` or a new chunk cannot be allocated.
+    pub fn try_alloc(&self, t: T) -> ArenaResult<UnsafeArenaRef<T>> {
+        let index = self.make_room()?;
         let chunks = unsafe { &mut *(self.chunks.get()) };
-        let chunks_count = chunks.len();
-        let chunk = chunks.last_mut().unwrap();
 
-        let (chunk_id, element_id, chunk) = if chunk.capacity() == chunk.len() {
-            chunks.push(Vec::with_capacity(self.capacity));
-            (chunks_count, 0, chunks.last_mut().unwrap())
-        } else {
-            (chunks_count - 1, chunk.len(), chunk)
+        unsafe { chunks.last_mut().unwrap().push(t) };
+
+        Ok(self.make_ref(index))
+    }
+
+    /// Make sure the last chunk has a free slot, and return the index of the element put there.
+    fn make_room(&self) -> ArenaResult<NonZeroU32> {
+        let len = self.slots();
+
+        if let Some(max_len) = self.max_len.filter(|max_len| len >= *max_len) {
+            return Err(ArenaError::TooManyElements(max_len));
+        }
+
+        let index = u32::try_from(len + 1)
+            .ok()
+            .and_then(NonZeroU32::new)
+            .ok_or(ArenaError::IndexOverflow)?;
+        if self.remaining_in_chunk() == 0 {
+            let chunk =
+                self.new_chunk(self.capacity(), self.policy.chunk_size(self.chunk_count()))?;
+
+            self.push_chunk(chunk);
+        }
+
+        Ok(index)
+    }
+
+    /// Claim the slot of a new element without writing it. The element is written through
+    /// `get_raw`, then `finish_uninit` is called; until then, the slot is vacant, so it is not
+    /// found by lookup nor visited by iteration, while the other elements still are.
+    /// # Panics
+    /// Panics if the allocation fails, see `try_alloc`.
+    pub fn alloc_uninit(&self) -> UnsafeArenaRef<T> {
+        self.try_alloc_uninit()
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Like `alloc_uninit`, but fails instead of panicking, see `try_alloc`.
+    pub fn try_alloc_uninit(&self) -> ArenaResult<UnsafeArenaRef<T>> {
+        let index = self.make_room()?;
+        let chunks = unsafe { &mut *(self.chunks.get()) };
+
+        // The slot is counted as an element, so elements allocated meanwhile come after it.
+        chunks.last_mut().unwrap().len += 1;
+        unsafe { &mut *(self.vacant.get()) }.push(index.get() as usize - 1);
+
+        Ok(self.make_ref(index))
+    }
+
+    /// Mark the slot of `r`, claimed by `alloc_uninit`, as initialized.
+    /// # Safety
+    /// The element of the slot must have been written, and the slot not be finished or abandoned.
+    pub unsafe fn finish_uninit(&self, r: &UnsafeArenaRef<T>) {
+        let vacant = &mut *(self.vacant.get());
+
+        if let Ok(pos) = vacant.binary_search(&(r.index.get() as usize - 1)) {
+            vacant.remove(pos);
+        }
+    }
+
+    /// Give up the slot of `r`, claimed by `alloc_uninit`.
+    /// The slot is freed if it is still the last one. Otherwise, it stays vacant: it is skipped by
+    /// iteration and drop, and its `Idx` is not found by lookup.
+    /// # Safety
+    /// `r` must be claimed by `alloc_uninit` of this arena, and not be finished or abandoned.
+    pub unsafe fn abandon_uninit(&self, r: &UnsafeArenaRef<T>) {
+        let chunks = &mut *(self.chunks.get());
+        let last = chunks.last_mut().unwrap();
+
+        if last.len > 0 && last.start + last.len == r.index.get() as usize {
+            last.len -= 1;
+            // The last slot has the greatest index.
+            (*self.vacant.get()).pop();
+        }
+    }
+
+    /// Allocate all items of `iter` contiguously in one chunk, starting a new chunk if they do not
+    /// fit in the rest of the last one.
+    /// # Panics
+    /// Panics if the allocation fails, see `try_alloc_extend`.
+    pub fn alloc_extend(&self, iter: impl IntoIterator<Item = T>) -> UnsafeArenaSliceRef<T> {
+        self.try_alloc_extend(iter)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Like `alloc_extend`, but fails like `try_alloc` instead of panicking.
+    /// The items are dropped on failure, and the rest of `iter` is not visited.
+    pub fn try_alloc_extend(
+        &self,
+        iter: impl IntoIterator<Item = T>,
+    ) -> ArenaResult<UnsafeArenaSliceRef<T>> {
+        let iter = iter.into_iter();
+        let start = self.slots();
+
+        self.run_end(start, iter.size_hint().0)?;
+        self.try_reserve(iter.size_hint().0)?;
+
+        let chunks = unsafe { &mut *(self.chunks.get()) };
+        let count = chunks.len();
+        let last = chunks.last_mut().unwrap();
+        let room = last.size - last.len;
+        let mut pending = Pending {
+            ptr: unsafe { last.ptr.as_ptr().add(last.len) },
+            len: 0,
         };
+        // With the last chunk cut down to its elements, an allocation made by `iter` starts a new
+        // chunk instead of taking the room of the items.
+        let regular = self.regular.get();
+        let mut cut = regular;
 
-        chunk.push(t);
+        last.size = last.len;
+        cut.check(&self.policy, chunks, count - 1);
+        self.regular.set(cut);
 
-        UnsafeArenaRef {
-            chunk: chunk_id,
-            elem: element_id,
-            __marker: Default::default(),
+        let staged = self.extend_pending(iter, start, room, count, &mut pending);
+        if self.chunk_count() == count {
+            // Nothing else was allocated, so the room is given back to the last chunk.
+            let chunks = unsafe { &mut *(self.chunks.get()) };
+
+            chunks.last_mut().unwrap().size += room;
+            self.regular.set(regular);
+        }
+
+        let Some(buf) = staged? else {
+            // The items follow the elements of the last chunk.
+            let end = self.run_end(start, pending.len)?;
+            let chunks = unsafe { &mut *(self.chunks.get()) };
+
+            chunks.last_mut().unwrap().len += mem::take(&mut pending.len);
+
+            return Ok(self.make_slice_ref(start, end));
+        };
+
+        let start = self.slots();
+        let end = self.run_end(start, buf.len())?;
+        if let Some(max_bytes) = self.max_bytes {
+            if (self.capacity() + buf.len()).saturating_mul(size_of::<T>()) > max_bytes {
+                return Err(ArenaError::TooManyBytes(max_bytes));
+            }
+        }
+        self.start_chunk(Chunk::from_vec(start, buf.len(), buf));
+
+        Ok(self.make_slice_ref(start, end))
+    }
+
+    /// Write the items of `iter` to `pending`, which has `room` slots, or move them to a buffer of
+    /// their own once `iter` allocated in the arena too, or they outgrow the room.
+    /// The buffer is returned if they were moved.
+    fn extend_pending(
+        &self,
+        iter: impl Iterator<Item = T>,
+        start: usize,
+        room: usize,
+        count: usize,
+        pending: &mut Pending<T>,
+    ) -> ArenaResult<Option<AllocVec<T, A>>> {
+        let mut staged: Option<AllocVec<T, A>> = None;
+
+        for (written, item) in iter.enumerate() {
+            self.run_end(start, written + 1)?;
+
+            if staged.is_none() && (pending.len == room || self.chunk_count() != count) {
+                staged = Some(self.stage(pending)?);
+            }
+            match &mut staged {
+                Some(buf) => {
+                    buf.try_reserve(1).map_err(|_| ArenaError::AllocFailed)?;
+                    buf.push(item);
+                }
+                None => {
+                    unsafe { pending.ptr.add(pending.len).write(item) };
+                    pending.len += 1;
+                }
+            }
+        }
+        if staged.is_none() && self.chunk_count() != count {
+            staged = Some(self.stage(pending)?);
+        }
+
+        Ok(staged)
+    }
+
+    /// Move the items of `pending` into a new buffer, where more items are pushed.
+    fn stage(&self, pending: &mut Pending<T>) -> ArenaResult<AllocVec<T, A>> {
+        let mut buf = AllocVec::new_in(self.alloc.clone());
+
+        buf.try_reserve(pending.len.max(1) * 2)
+            .map_err(|_| ArenaError::AllocFailed)?;
+        unsafe {
+            ptr::copy_nonoverlapping(pending.ptr, buf.as_mut_ptr(), pending.len);
+            buf.set_len(mem::take(&mut pending.len));
+        }
+
+        Ok(buf)
+    }
+
+    /// Check that a run of `len` elements can start at the `start`-th slot, and return its end.
+    fn run_end(&self, start: usize, len: usize) -> ArenaResult<u32> {
+        let end = start.saturating_add(len);
+
+        if let Some(max_len) = self.max_len.filter(|max_len| end > *max_len) {
+            return Err(ArenaError::TooManyElements(max_len));
+        }
+
+        // The index of an element is stored shifted by one, so `end` itself must fit.
+        u32::try_from(end).map_err(|_| ArenaError::IndexOverflow)
+    }
+
+    fn make_slice_ref(&self, start: usize, end: u32) -> UnsafeArenaSliceRef<T> {
+        UnsafeArenaSliceRef {
+            start: start as u32,
+            len: end - start as u32,
+            #[cfg(any(debug_assertions, feature = "checked"))]
+            arena: self.id,
+            __marker: PhantomData,
+        }
+    }
+
+    /// Allocate a run that will be handed out through a mutable ref, see `alloc_extend`.
+    /// The ref must be released by `release_mut_slice` once it is dropped or frozen.
+    /// # Panics
+    /// Panics if the allocation fails, see `try_alloc_extend`.
+    pub fn alloc_extend_mut(&self, iter: impl IntoIterator<Item = T>) -> UnsafeArenaSliceRef<T> {
+        self.try_alloc_extend_mut(iter)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Like `alloc_extend_mut`, but fails instead of panicking, see `try_alloc_extend`.
+    pub fn try_alloc_extend_mut(
+        &self,
+        iter: impl IntoIterator<Item = T>,
+    ) -> ArenaResult<UnsafeArenaSliceRef<T>> {
+        let r = self.try_alloc_extend(iter)?;
+        self.borrow(r.start as usize..(r.start + r.len) as usize);
+
+        Ok(r)
+    }
+
+    /// Allocate an element that will be handed out through a mutable ref.
+    /// The ref must be released by `release_mut` once it is dropped or frozen.
+    /// # Panics
+    /// Panics if the allocation fails, see `try_alloc`.
+    pub fn alloc_mut(&self, t: T) -> UnsafeArenaRef<T> {
+        self.try_alloc_mut(t)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Like `alloc_mut`, but fails instead of panicking, see `try_alloc`.
+    pub fn try_alloc_mut(&self, t: T) -> ArenaResult<UnsafeArenaRef<T>> {
+        let r = self.try_alloc(t)?;
+        self.borrow_one(&r);
+
+        Ok(r)
+    }
+
+    /// Limit the number of elements, or lift the limit with `None`.
+    pub fn set_max_len(&mut self, max_len: Option<usize>) {
+        self.max_len = max_len;
+    }
+
+    /// Limit the bytes taken by chunks started from now on, or lift the limit with `None`.
+    /// Chunks are cut down to fit into the limit.
+    pub fn set_max_bytes(&mut self, max_bytes: Option<usize>) {
+        self.max_bytes = max_bytes;
+    }
+
+    /// Release a mutable ref handed out by `alloc_mut` or `thaw`.
+    /// # Safety
+    /// Every call must match exactly one earlier `alloc_mut` or successful `thaw` of `r`.
+    pub unsafe fn release_mut(&self, r: &UnsafeArenaRef<T>) {
+        let index = r.index.get() as usize - 1;
+
+        self.release(index..index + 1);
+    }
+
+    /// Release a mutable ref handed out by `alloc_extend_mut`.
+    /// # Safety
+    /// Every call must match exactly one earlier `alloc_extend_mut` returning `r`.
+    pub unsafe fn release_mut_slice(&self, r: &UnsafeArenaSliceRef<T>) {
+        self.release(r.start as usize..(r.start + r.len) as usize);
+    }
+
+    fn borrow_one(&self, r: &UnsafeArenaRef<T>) {
+        let index = r.index.get() as usize - 1;
+
+        self.borrow(index..index + 1);
+    }
+
+    fn borrow(&self, range: Range<usize>) {
+        unsafe { &mut *(self.borrowed.get()) }.push(range);
+    }
+
+    fn release(&self, range: Range<usize>) {
+        let borrowed = unsafe { &mut *(self.borrowed.get()) };
+
+        // Refs are mostly dropped in the reverse order they were handed out.
+        if let Some(pos) = borrowed.iter().rposition(|borrow| *borrow == range) {
+            borrowed.swap_remove(pos);
         }
     }
 
+    /// Whether a live mutable ref points to the `index`-th element.
+    fn is_borrowed(&self, index: usize) -> bool {
+        unsafe { &*(self.borrowed.get()) }
+            .iter()
+            .any(|borrow| borrow.contains(&index))
+    }
+
+    /// # Panics
+    /// Panics while any mutable ref of this arena is alive. Elements reached by iteration might be
+    /// the ones those refs point to.
+    pub fn assert_no_mut_refs(&self) {
+        assert!(
+            unsafe { &*(self.borrowed.get()) }.is_empty(),
+            "cannot iterate elements while mutable refs of the arena are alive"
+        );
+    }
+
+    /// Forget the refs handed out so far, once none of them can be alive anymore.
+    pub(crate) fn forget_mut_refs(&mut self) {
+        self.borrowed.get_mut().clear();
+        self.shared.set(0);
+    }
+
+    /// Count `r` as a mutable ref if no other ref can point to its element, since it was not
+    /// reached by lookup or iteration. Returns whether it can be used as a mutable ref.
+    /// # Safety
+    /// `r` must be constructed by this arena and handed out by an allocation, and only be used
+    /// as a mutable ref from now on if this returns `true`.
+    pub unsafe fn thaw(&self, r: &UnsafeArenaRef<T>) -> bool {
+        let unshared = r.index.get() as usize > self.shared.get();
+
+        if unshared {
+            self.borrow_one(r);
+        }
+        unshared
+    }
+
+    /// Resolve an `Idx` into a ref that can be read through `get`.
+    /// Returns `None` if `idx` is out of bounds or vacant.
+    /// # Panics
+    /// Panics while a mutable ref to the element of `idx` is alive. A ref that was forgotten
+    /// instead of dropped keeps its element unreachable until the arena is reset or rolled back.
+    pub fn lookup(&self, idx: Idx<T>) -> Option<UnsafeArenaRef<T>> {
+        assert!(
+            !self.is_borrowed(idx.index()),
+            "cannot look up an element while a mutable ref to it is alive"
+        );
+
+        let r = self.lookup_untracked(idx)?;
+        self.shared.set(self.shared.get().max(idx.index() + 1));
+
+        Some(r)
+    }
+
+    /// Resolve an `Idx` like `lookup`, but without checking for mutable refs or recording the
+    /// element as shared, so the arena is only read. Returns `None` if `idx` is out of bounds or
+    /// vacant. Meant for arenas that are read from several threads, such as a frozen one.
+    pub fn lookup_untracked(&self, idx: Idx<T>) -> Option<UnsafeArenaRef<T>> {
+        let found = idx.index() < self.slots() && !self.is_vacant(idx.index());
+
+        found.then(|| self.make_ref(idx.raw()))
+    }
+
+    /// Resolve an `Idx` into a mutable reference, or `None` if it is out of bounds or vacant.
+    pub fn lookup_mut(&mut self, idx: Idx<T>) -> Option<&mut T> {
+        if self.is_vacant(idx.index()) {
+            return None;
+        }
+
+        let chunks = unsafe { &*(self.chunks.get()) };
+        let (chunk, elem) = self.locate(chunks, idx.index());
+        let chunk = chunks.get(chunk)?;
+
+        (elem < chunk.len).then(|| unsafe { &mut *chunk.slot(elem) })
+    }
+
+    /// Whether the slot of the `index`-th element was claimed by `alloc_uninit` and holds no
+    /// element.
+    fn is_vacant(&self, index: usize) -> bool {
+        unsafe { &*(self.vacant.get()) }
+            .binary_search(&index)
+            .is_ok()
+    }
+
+    /// Indices of the elements among the first `len` slots, skipping the vacant ones.
+    /// The vacant slots are copied, so the arena can still allocate while iterating.
+    fn occupied(&self, len: usize) -> Occupied {
+        let vacant = unsafe { &*(self.vacant.get()) };
+
+        Occupied {
+            range: 0..len,
+            vacant: vacant_in(vacant, 0..len).to_vec().into_iter(),
+        }
+    }
+
+    /// Pointers to the runs of contiguous elements among the first `len` slots, with their
+    /// lengths. Runs are split at chunk boundaries and around vacant slots.
+    fn runs(&self, len: usize) -> impl Iterator<Item = (*mut T, usize)> + '_ {
+        let vacant = unsafe { &*(self.vacant.get()) };
+        let mut vacant = vacant_in(vacant, 0..len).to_vec().into_iter();
+        let mut index = 0;
+
+        iter::from_fn(move || loop {
+            if index >= len {
+                return None;
+            }
+            if vacant.as_slice().first() == Some(&index) {
+                vacant.next();
+                index += 1;
+                continue;
+            }
+
+            // The chunk list itself may be reallocated by `alloc` meanwhile, so it is looked up
+            // anew for every run; the buffers never move.
+            let chunks = unsafe { &*(self.chunks.get()) };
+            let (chunk, elem) = self.locate(chunks, index);
+            let chunk = &chunks[chunk];
+            let next_vacant = vacant.as_slice().first().copied().unwrap_or(len);
+            let end = (chunk.start + chunk.len).min(len).min(next_vacant);
+            let run = (unsafe { chunk.ptr.as_ptr().add(elem) }, end - index);
+
+            index = end;
+            return Some(run);
+        })
+    }
+
+    /// Refs to all elements allocated so far, in allocation order.
+    /// Elements allocated while iterating are not visited.
+    pub fn refs(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = UnsafeArenaRef<T>> + ExactSizeIterator + '_ {
+        let len = self.slots();
+
+        self.shared.set(len);
+        // The length always fits in `u32`.
+        self.occupied(len)
+            .map(|index| self.make_ref(NonZeroU32::new(index as u32 + 1).unwrap()))
+    }
+
+    /// Iterate over all elements allocated so far, in allocation order.
+    /// # Panics
+    /// Panics while any mutable ref of this arena is alive, since it might point to a visited element.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> + ExactSizeIterator + '_ {
+        self.assert_no_mut_refs();
+
+        self.refs().map(|r| unsafe { &*self.get_raw(&r) })
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> + '_ {
+        self.as_mut_slices().flatten()
+    }
+
+    /// The elements allocated so far, as one slice per chunk in allocation order.
+    /// A chunk is split into several slices around vacant slots.
+    /// Elements allocated while iterating are not visited.
+    /// # Panics
+    /// Panics while any mutable ref of this arena is alive, since it might point into a slice.
+    pub fn as_slices(&self) -> impl Iterator<Item = &[T]> + '_ {
+        self.assert_no_mut_refs();
+
+        let len = self.slots();
+        self.shared.set(len);
+
+        self.runs(len)
+            .map(|(ptr, len)| unsafe { slice::from_raw_parts(ptr, len) })
+    }
+
+    /// The elements, as one mutable slice per chunk in allocation order.
+    /// A chunk is split into several slices around vacant slots.
+    pub fn as_mut_slices(&mut self) -> impl Iterator<Item = &mut [T]> + '_ {
+        let len = self.slots();
+
+        self.runs(len)
+            .map(|(ptr, len)| unsafe { slice::from_raw_parts_mut(ptr, len) })
+    }
+
+    /// The number of elements, not counting vacant slots.
     pub fn len(&self) -> usize {
+        self.slots() - unsafe { &*(self.vacant.get()) }.len()
+    }
+
+    /// The number of slots claimed so far, vacant or not; the next one gets this index.
+    fn slots(&self) -> usize {
         let chunks = unsafe { &*(self.chunks.get()) };
-        let chunks_count = chunks.len();
         let chunk = chunks.last().unwrap();
 
-        self.capacity * (chunks_count - 1) + chunk.len()
+        chunk.start + chunk.len
     }
 
     pub fn is_empty(&self) -> bool {
-        let chunks = unsafe { &*(self.chunks.get()) };
-        chunks.len() == 1 && chunks.last().unwrap().is_empty()
+        self.len() == 0
     }
 
+    /// Total number of slots over all chunks.
     pub fn capacity(&self) -> usize {
         let chunks = unsafe { &*(self.chunks.get()) };
         let chunk = chunks.last().unwrap();
 
-        chunk.capacity()
+        chunk.start + chunk.size
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        unsafe { &*(self.chunks.get()) }.len()
+    }
+
+    /// Number of elements that can still be allocated before a new chunk is started.
+    pub fn remaining_in_chunk(&self) -> usize {
+        let chunks = unsafe { &*(self.chunks.get()) };
+        let chunk = chunks.last().unwrap();
+
+        chunk.size - chunk.len
+    }
+
+    /// Make sure the next `additional` allocations do not start a new chunk.
+    /// If the last chunk is too small, the rest of it is left unused and a chunk of at least
+    /// `additional` elements is started.
+    /// # Panics
+    /// Panics if the chunk cannot be started, see `try_reserve`.
+    pub fn reserve(&self, additional: usize) {
+        self.try_reserve(additional)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Like `reserve`, but fails if a chunk of `additional` elements does not fit into
+    /// `max_bytes` or cannot be allocated.
+    pub fn try_reserve(&self, additional: usize) -> ArenaResult<()> {
+        if self.remaining_in_chunk() >= additional {
+            return Ok(());
+        }
+
+        let size = additional.max(self.policy.chunk_size(self.chunk_count()));
+        let chunk = self.new_chunk(self.slots(), size)?;
+
+        if chunk.size < additional {
+            // The buffer is kept for later chunks, like the ones kept by `reset`.
+            let mut spare = self.spare.take();
+
+            spare.push(chunk);
+            self.spare.set(spare);
+            // Only cut down by `new_chunk` when there is a byte limit.
+            return Err(ArenaError::TooManyBytes(self.max_bytes.unwrap_or_default()));
+        }
+
+        self.start_chunk(chunk);
+
+        Ok(())
+    }
+
+    /// Allocate in `chunk` from now on, leaving the rest of the last chunk unused, or dropping the
+    /// last chunk if it holds nothing.
+    fn start_chunk(&self, chunk: Chunk<T, A>) {
+        // Only borrowed once the caller is done looking at the chunks.
+        let chunks = unsafe { &mut *(self.chunks.get()) };
+        let last = chunks.last_mut().unwrap();
+
+        // A chunk cut down to nothing by `try_alloc_extend` still holds its items.
+        if last.len == 0 && last.size > 0 {
+            chunks.pop();
+            chunks.push(chunk);
+            self.regular.set(Regular::find(&self.policy, chunks));
+        } else {
+            let mut regular = self.regular.get();
+
+            last.size = last.len;
+            regular.check(&self.policy, chunks, chunks.len() - 1);
+            chunks.push(chunk);
+            regular.check(&self.policy, chunks, chunks.len() - 1);
+            self.regular.set(regular);
+        }
+    }
+
+    /// Drop all elements, keeping every chunk buffer to be reused by later allocations.
+    /// Every `Idx` of the arena becomes invalid, and may point to a later element.
+    pub fn reset(&mut self) {
+        let chunks = self.chunks.get_mut();
+        let spare = self.spare.get_mut();
+        let vacant = self.vacant.get_mut();
+
+        for mut chunk in chunks.drain(1..).rev() {
+            chunk.truncate(0, vacant_in(vacant, chunk.start..chunk.start + chunk.len));
+            spare.push(chunk);
+        }
+
+        let first = &mut chunks[0];
+        let size = self.policy.chunk_size(0);
+
+        first.truncate(0, vacant_in(vacant, 0..first.len));
+        vacant.clear();
+        if first.capacity >= size {
+            first.size = size;
+        } else {
+            *first = Chunk::new(0, size, self.alloc.clone());
+        }
+        self.regular.set(Regular::ALL);
+        self.resets = self.resets.wrapping_add(1);
+        self.forget_mut_refs();
+    }
+
+    pub fn checkpoint(&self) -> Checkpoint<T> {
+        Checkpoint {
+            len: self.slots(),
+            arena: self.id,
+            resets: self.resets,
+            __marker: PhantomData,
+        }
+    }
+
+    /// Drop every element allocated after `checkpoint`. Their chunks are kept like by `reset`.
+    /// Every `Idx` of a dropped element becomes invalid, and may point to a later element.
+    /// # Panics
+    /// Panics if `checkpoint` was created by another arena, or if the arena has already been rolled
+    /// back or reset past it.
+    pub fn rollback(&mut self, checkpoint: Checkpoint<T>) {
+        assert_eq!(
+            checkpoint.arena, self.id,
+            "checkpoint is used with an arena that did not create it"
+        );
+        assert_eq!(
+            checkpoint.resets, self.resets,
+            "arena has been reset past the checkpoint"
+        );
+        assert!(
+            checkpoint.len <= self.slots(),
+            "arena has been rolled back past the checkpoint"
+        );
+
+        let len = checkpoint.len;
+        let chunks = self.chunks.get_mut();
+        let spare = self.spare.get_mut();
+        let vacant = self.vacant.get_mut();
+        let kept = chunks.partition_point(|c| c.start <= len);
+
+        for mut chunk in chunks.drain(kept..).rev() {
+            chunk.truncate(0, vacant_in(vacant, chunk.start..chunk.start + chunk.len));
+            spare.push(chunk);
+        }
+
+        let last = chunks.last_mut().unwrap();
+        last.truncate(
+            len - last.start,
+            vacant_in(vacant, len..last.start + last.len),
+        );
+        vacant.truncate(vacant.partition_point(|&index| index < len));
+        *self.regular.get_mut() = Regular::find(&self.policy, chunks);
+        self.forget_mut_refs();
+    }
+
+    /// Release the unused slots of the last chunk, and the buffers kept by `reset`.
+    pub fn shrink_to_fit(&mut self) {
+        self.spare.get_mut().clear();
+
+        let chunks = self.chunks.get_mut();
+
+        if chunks.len() > 1 && chunks.last().unwrap().len == 0 {
+            chunks.pop();
+            *self.regular.get_mut() = Regular::find(&self.policy, chunks);
+            return;
+        }
+
+        let pos = chunks.len() - 1;
+        let last = &mut chunks[pos];
+
+        if last.len < last.size {
+            last.shrink_to_fit();
+            self.regular.get_mut().check(&self.policy, chunks, pos);
+        }
+    }
+}
+
+impl<T, A: Allocator> IntoIterator for UnsafeArena<T, A> {
+    type Item = T;
+    type IntoIter = UnsafeArenaIntoIter<T, A>;
+
+    fn into_iter(mut self) -> UnsafeArenaIntoIter<T, A> {
+        let vacant = mem::take(self.vacant.get_mut());
+        let chunks: Vec<_> = mem::take(self.chunks.get_mut())
+            .into_iter()
+            .map(|mut chunk| {
+                chunk.close_vacant(vacant_in(&vacant, chunk.start..chunk.start + chunk.len));
+                chunk.into_vec()
+            })
+            .collect();
+
+        UnsafeArenaIntoIter {
+            inner: chunks.into_iter().flatten(),
+        }
+    }
+}
+
+impl<T, A: Allocator> Iterator for UnsafeArenaIntoIter<T, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.inner.next()
     }
 }
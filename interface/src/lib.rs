@@ -1,7 +1,86 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 mod basic;
+mod branded;
+mod error;
+mod frozen;
+mod generational;
+mod idx;
 mod internal;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod policy;
+mod scope;
+mod slice;
+// Locking the chunk list needs `std::sync::Mutex`.
+#[cfg(feature = "std")]
+mod sync;
+mod tagged;
 mod traits;
 
 pub use basic::*;
+pub use branded::*;
+pub use error::*;
+pub use frozen::*;
+pub use generational::*;
+pub use idx::*;
 pub use internal::*;
+#[cfg(feature = "rayon")]
+pub use parallel::*;
+pub use policy::*;
+pub use scope::*;
+pub use slice::*;
+#[cfg(feature = "std")]
+pub use sync::*;
+pub use tagged::*;
 pub use traits::*;
+
+pub use allocator_api2;
+pub use allocator_api2::alloc::{Allocator, Global};
+
+#[cfg(feature = "rayon")]
+pub use rayon;
+
+/// Expand to the given items only when the `rayon` feature of this crate is enabled. The derive
+/// wraps its parallel iteration in it, since it cannot see the features of this crate.
+#[doc(hidden)]
+#[cfg(feature = "rayon")]
+#[macro_export]
+macro_rules! __if_rayon {
+    ($($item:item)*) => {
+        $($item)*
+    };
+}
+
+#[doc(hidden)]
+#[cfg(not(feature = "rayon"))]
+#[macro_export]
+macro_rules! __if_rayon {
+    ($($item:item)*) => {};
+}
+
+/// Expand to the given items only when the `std` feature of this crate is enabled, and to a
+/// compile error saying that `$what` requires it otherwise. The derive wraps concurrent arenas in
+/// it, since their storage locks its chunk list with `std::sync::Mutex`.
+#[doc(hidden)]
+#[cfg(feature = "std")]
+#[macro_export]
+macro_rules! __if_std {
+    ($what:literal; $($item:item)*) => {
+        $($item)*
+    };
+}
+
+#[doc(hidden)]
+#[cfg(not(feature = "std"))]
+#[macro_export]
+macro_rules! __if_std {
+    ($what:literal; $($item:item)*) => {
+        ::core::compile_error!(::core::concat!(
+            $what,
+            " requires the `std` feature of enum-arena"
+        ));
+    };
+}
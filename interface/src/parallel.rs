@@ -0,0 +1,45 @@
+use allocator_api2::alloc::Allocator;
+use rayon::prelude::*;
+
+use crate::internal::UnsafeArena;
+
+/// Iterate in parallel over the elements of `slices`, handing out whole slices to each task.
+pub fn par_flatten<'a, T: Sync + 'a>(
+    slices: impl Iterator<Item = &'a [T]>,
+) -> impl ParallelIterator<Item = &'a T> {
+    let slices: Vec<_> = slices.collect();
+
+    slices.into_par_iter().flat_map_iter(|slice| slice.iter())
+}
+
+/// Iterate mutably in parallel over the elements of `slices`, handing out whole slices to each
+/// task.
+pub fn par_flatten_mut<'a, T: Send + 'a>(
+    slices: impl Iterator<Item = &'a mut [T]>,
+) -> impl ParallelIterator<Item = &'a mut T> {
+    let slices: Vec<_> = slices.collect();
+
+    slices
+        .into_par_iter()
+        .flat_map_iter(|slice| slice.iter_mut())
+}
+
+impl<T, A: Allocator + Clone> UnsafeArena<T, A> {
+    /// Iterate over all elements in parallel, handing out whole chunks to each task.
+    /// # Panics
+    /// Panics while any mutable ref of this arena is alive.
+    pub fn par_iter(&self) -> impl ParallelIterator<Item = &T> + '_
+    where
+        T: Sync,
+    {
+        par_flatten(self.as_slices())
+    }
+
+    /// Iterate over all elements mutably in parallel, handing out whole chunks to each task.
+    pub fn par_iter_mut(&mut self) -> impl ParallelIterator<Item = &mut T> + '_
+    where
+        T: Send,
+    {
+        par_flatten_mut(self.as_mut_slices())
+    }
+}
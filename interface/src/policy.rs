@@ -0,0 +1,95 @@
+/// How many elements each chunk of an arena holds.
+/// Every size is at least one.
+#[derive(Clone, Copy, Debug)]
+pub enum ChunkPolicy {
+    /// Every chunk holds the same number of elements.
+    Fixed(usize),
+    /// The first chunk holds `initial` elements, and every next one twice as many, up to `max`.
+    Doubling { initial: usize, max: usize },
+    /// The n-th chunk holds as many elements as the function returns for n.
+    Custom(fn(usize) -> usize),
+}
+
+impl ChunkPolicy {
+    /// Number of elements the `chunk`-th chunk holds.
+    pub fn chunk_size(&self, chunk: usize) -> usize {
+        match *self {
+            ChunkPolicy::Fixed(size) => size.max(1),
+            ChunkPolicy::Doubling { initial, max } => {
+                let (initial, max) = Self::doubling_bounds(initial, max);
+
+                u32::try_from(chunk)
+                    .ok()
+                    .and_then(|chunk| 1usize.checked_shl(chunk))
+                    .and_then(|factor| initial.checked_mul(factor))
+                    .map_or(max, |size| size.min(max))
+            }
+            ChunkPolicy::Custom(f) => f(chunk).max(1),
+        }
+    }
+
+    /// Find the chunk and the position inside it of the `index`-th element in O(1),
+    /// provided that every chunk holds exactly `chunk_size` elements.
+    /// Returns `None` for `Custom`, whose chunks have to be searched.
+    pub(crate) fn locate(&self, index: usize) -> Option<(usize, usize)> {
+        match *self {
+            ChunkPolicy::Fixed(size) => {
+                let size = size.max(1);
+
+                Some((index / size, index % size))
+            }
+            ChunkPolicy::Doubling { initial, max } => {
+                let (initial, max) = Self::doubling_bounds(initial, max);
+                // Chunks before `doubled` are exactly `initial << chunk` long and hold
+                // `initial * (2^doubled - 1)` elements in total; all later ones are `max` long.
+                let ratio = max.div_ceil(initial);
+                let doubled = (usize::BITS - (ratio - 1).leading_zeros()) as usize;
+                let geometric = 1usize
+                    .checked_shl(doubled as u32)
+                    .map_or(usize::MAX, |factor| initial.saturating_mul(factor - 1));
+
+                if index < geometric {
+                    let chunk = (index / initial + 1).ilog2() as usize;
+
+                    Some((chunk, index - initial * ((1 << chunk) - 1)))
+                } else {
+                    let rest = index - geometric;
+
+                    Some((doubled + rest / max, rest % max))
+                }
+            }
+            ChunkPolicy::Custom(_) => None,
+        }
+    }
+
+    /// Index of the first element of the `chunk`-th chunk in O(1), provided that every chunk
+    /// before it holds exactly `chunk_size` elements. Returns `None` for `Custom`.
+    pub(crate) fn chunk_start(&self, chunk: usize) -> Option<usize> {
+        match *self {
+            ChunkPolicy::Fixed(size) => Some(chunk.saturating_mul(size.max(1))),
+            ChunkPolicy::Doubling { initial, max } => {
+                let (initial, max) = Self::doubling_bounds(initial, max);
+                let ratio = max.div_ceil(initial);
+                let doubled = (usize::BITS - (ratio - 1).leading_zeros()) as usize;
+                let geometric = |chunk: usize| {
+                    1usize
+                        .checked_shl(chunk as u32)
+                        .map_or(usize::MAX, |factor| initial.saturating_mul(factor - 1))
+                };
+
+                if chunk < doubled {
+                    Some(geometric(chunk))
+                } else {
+                    Some(geometric(doubled).saturating_add((chunk - doubled).saturating_mul(max)))
+                }
+            }
+            ChunkPolicy::Custom(_) => None,
+        }
+    }
+
+    fn doubling_bounds(initial: usize, max: usize) -> (usize, usize) {
+        let initial = initial.max(1);
+
+        (initial, max.max(initial))
+    }
+}
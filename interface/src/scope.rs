@@ -0,0 +1,125 @@
+use core::{
+    marker::PhantomData,
+    ops::{Deref, Index},
+};
+
+use crate::branded::Brand;
+use crate::idx::Idx;
+use crate::internal::Checkpoint;
+
+/// Arena that can allocate on top of itself for a while with `scope`.
+pub trait ScopedArena<T>: Index<Idx<T>, Output = T> {
+    fn alloc_idx(&self, t: T) -> Idx<T>;
+
+    fn checkpoint(&self) -> Checkpoint<T>;
+
+    fn rollback(&mut self, checkpoint: Checkpoint<T>);
+
+    /// Run `f` with a `Scope` of this arena, then drop everything allocated in `f`, even if it
+    /// panics.
+    ///
+    /// The scope is this arena itself, not a nested child arena: elements allocated before
+    /// stay reachable through it, and it allocates on top of them. No ref of it can escape `f`,
+    /// and neither can an `Idx` allocated by `Scope::alloc_idx`, which is branded by the scope:
+    ///
+    /// ```compile_fail
+    /// use enum_arena_interface::*;
+    ///
+    /// let mut arena = BasicArena::new(4);
+    /// let idx = arena.scope(|tmp| tmp.alloc_idx(1u64));
+    /// ```
+    ///
+    /// An `Idx` taken from a ref with `to_idx` is not branded, so it can escape; once the scope
+    /// is over, it points past the end of the arena, or to a later element.
+    fn scope<R>(&mut self, f: impl for<'scope> FnOnce(&Scope<'scope, Self>) -> R) -> R
+    where
+        Self: Sized,
+    {
+        let guard = Rollback {
+            checkpoint: self.checkpoint(),
+            arena: self,
+        };
+
+        f(&Scope {
+            arena: &*guard.arena,
+            __brand: PhantomData,
+        })
+    }
+}
+
+/// Rolls an arena back once dropped, so a scope is undone even if it panics.
+struct Rollback<'a, T, S: ScopedArena<T>> {
+    arena: &'a mut S,
+    checkpoint: Checkpoint<T>,
+}
+
+impl<'a, T, S: ScopedArena<T>> Drop for Rollback<'a, T, S> {
+    fn drop(&mut self) {
+        self.arena.rollback(self.checkpoint);
+    }
+}
+
+/// Arena borrowed by `ScopedArena::scope`, which derefs to it.
+pub struct Scope<'scope, S> {
+    arena: &'scope S,
+    __brand: Brand<'scope>,
+}
+
+/// Handle of an element allocated in a `Scope`, which cannot outlive it.
+pub struct ScopedIdx<'scope, T> {
+    inner: Idx<T>,
+    __brand: Brand<'scope>,
+}
+
+impl<'scope, S> Scope<'scope, S> {
+    pub fn alloc_idx<T>(&self, t: T) -> ScopedIdx<'scope, T>
+    where
+        S: ScopedArena<T>,
+    {
+        ScopedIdx {
+            inner: self.arena.alloc_idx(t),
+            __brand: PhantomData,
+        }
+    }
+}
+
+impl<'scope, S> Deref for Scope<'scope, S> {
+    type Target = S;
+
+    fn deref(&self) -> &S {
+        self.arena
+    }
+}
+
+// Indexing does not go through `Deref` once the scope can be indexed at all.
+impl<'scope, T, S: ScopedArena<T>> Index<Idx<T>> for Scope<'scope, S> {
+    type Output = T;
+
+    fn index(&self, idx: Idx<T>) -> &T {
+        &self.arena[idx]
+    }
+}
+
+impl<'scope, T, S: ScopedArena<T>> Index<ScopedIdx<'scope, T>> for Scope<'scope, S> {
+    type Output = T;
+
+    fn index(&self, idx: ScopedIdx<'scope, T>) -> &T {
+        &self.arena[idx.inner]
+    }
+}
+
+impl<'scope, T> Clone for ScopedIdx<'scope, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'scope, T> Copy for ScopedIdx<'scope, T> {}
+
+impl<'scope, T> PartialEq for ScopedIdx<'scope, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<'scope, T> Eq for ScopedIdx<'scope, T> {}
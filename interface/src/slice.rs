@@ -0,0 +1,74 @@
+use core::ops::{Deref, DerefMut};
+
+use allocator_api2::alloc::{Allocator, Global};
+
+use crate::internal::*;
+
+/// Ref to a run of elements allocated together by `alloc_extend`.
+pub struct ArenaSliceRef<'arena, T, A: Allocator + Clone = Global> {
+    arena: &'arena UnsafeArena<T, A>,
+    inner: UnsafeArenaSliceRef<T>,
+}
+
+/// Mutable ref to a run of elements allocated together by `alloc_extend_mut`.
+pub struct ArenaMutSliceRef<'arena, T, A: Allocator + Clone = Global> {
+    arena: &'arena UnsafeArena<T, A>,
+    inner: UnsafeArenaSliceRef<T>,
+}
+
+impl<'arena, T, A: Allocator + Clone> ArenaSliceRef<'arena, T, A> {
+    /// # Safety
+    /// `inner` must be constructed by `arena`.
+    pub unsafe fn from_raw(
+        arena: &'arena UnsafeArena<T, A>,
+        inner: UnsafeArenaSliceRef<T>,
+    ) -> Self {
+        ArenaSliceRef { arena, inner }
+    }
+}
+
+impl<'arena, T, A: Allocator + Clone> ArenaMutSliceRef<'arena, T, A> {
+    /// # Safety
+    /// `inner` must be constructed by `arena` through `alloc_extend_mut`, and not be wrapped twice.
+    pub unsafe fn from_raw(
+        arena: &'arena UnsafeArena<T, A>,
+        inner: UnsafeArenaSliceRef<T>,
+    ) -> Self {
+        ArenaMutSliceRef { arena, inner }
+    }
+
+    pub fn freeze(self) -> ArenaSliceRef<'arena, T, A> {
+        ArenaSliceRef {
+            arena: self.arena,
+            inner: self.inner,
+        }
+    }
+}
+
+impl<'arena, T, A: Allocator + Clone> Drop for ArenaMutSliceRef<'arena, T, A> {
+    fn drop(&mut self) {
+        unsafe { self.arena.release_mut_slice(&self.inner) }
+    }
+}
+
+impl<'arena, T, A: Allocator + Clone> Deref for ArenaSliceRef<'arena, T, A> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { &*self.arena.get_slice_raw(&self.inner) }
+    }
+}
+
+impl<'arena, T, A: Allocator + Clone> Deref for ArenaMutSliceRef<'arena, T, A> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { &*self.arena.get_slice_raw(&self.inner) }
+    }
+}
+
+impl<'arena, T, A: Allocator + Clone> DerefMut for ArenaMutSliceRef<'arena, T, A> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { &mut *self.arena.get_slice_raw(&self.inner) }
+    }
+}
@@ -0,0 +1,524 @@
+use std::{
+    marker::PhantomData,
+    mem::{size_of, ManuallyDrop},
+    ops::{Deref, DerefMut},
+    ptr::{self, NonNull},
+    sync::{
+        atomic::{AtomicPtr, AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use crate::error::{ArenaError, ArenaResult};
+use crate::policy::ChunkPolicy;
+use crate::traits::*;
+
+/// Chunk of an `UnsafeSyncArena`. Slots are claimed by bumping `reserved`, which may overshoot
+/// `capacity` when several threads race for the last slots.
+struct SyncChunk<T> {
+    ptr: NonNull<T>,
+    reserved: AtomicUsize,
+    /// Number of slots that can be claimed, which `max_len` may cut below `buf_capacity`.
+    capacity: usize,
+    buf_capacity: usize,
+    /// Index of the first slot, counting the slots of all chunks before.
+    start: usize,
+}
+
+/// Storage of `SyncArena`, which can allocate from many threads at once.
+/// Allocation bumps an atomic cursor of the current chunk; a lock is only taken to start a new chunk.
+pub struct UnsafeSyncArena<T> {
+    current: AtomicPtr<SyncChunk<T>>,
+    chunks: Mutex<Vec<NonNull<SyncChunk<T>>>>,
+    policy: ChunkPolicy,
+    /// Cap on the slots of all chunks, and so on the number of elements.
+    max_len: Option<usize>,
+    /// Cap on the bytes of all chunk buffers, counting the slots of each chunk.
+    max_bytes: Option<usize>,
+    __marker: PhantomData<T>,
+}
+
+/// Element of an `UnsafeSyncArena`, pointing at its slot directly.
+pub struct UnsafeSyncArenaRef<T> {
+    ptr: NonNull<T>,
+}
+
+/// Arena that can be shared between threads and allocated from all of them at once.
+///
+/// Its refs are `Send + Sync` only when `T: Send + Sync`. A ref can be read from any thread it is
+/// sent to, which needs `T: Sync`. It also borrows the arena, through which any thread can allocate
+/// elements that are dropped wherever the arena is, and a mutable ref hands out `&mut T`, so
+/// elements move between threads in effect, which needs `T: Send`.
+///
+/// Only allocation is supported: elements have no `Idx`, and cannot be iterated, rolled back or
+/// reset, nor allocated in place or in contiguous runs.
+pub struct SyncArena<T> {
+    inner: UnsafeSyncArena<T>,
+}
+
+/// Immutable ref of a `SyncArena`, which is `Send + Sync` when `T: Send + Sync`.
+pub struct SyncArenaRef<'arena, T> {
+    arena: &'arena SyncArena<T>,
+    inner: UnsafeSyncArenaRef<T>,
+}
+
+/// Mutable ref of a `SyncArena`, which is `Send + Sync` when `T: Send + Sync`.
+pub struct SyncArenaMutRef<'arena, T> {
+    arena: &'arena SyncArena<T>,
+    inner: UnsafeSyncArenaRef<T>,
+}
+
+unsafe impl<T: Send> Send for UnsafeSyncArena<T> {}
+
+unsafe impl<T: Send + Sync> Sync for UnsafeSyncArena<T> {}
+
+unsafe impl<T: Sync> Send for UnsafeSyncArenaRef<T> {}
+
+unsafe impl<T: Sync> Sync for UnsafeSyncArenaRef<T> {}
+
+impl<T> SyncChunk<T> {
+    fn alloc(capacity: usize) -> NonNull<Self> {
+        Self::from_vec(Vec::with_capacity(capacity), 0, usize::MAX)
+    }
+
+    fn try_alloc(start: usize, capacity: usize, max_slots: usize) -> ArenaResult<NonNull<Self>> {
+        let mut buf = Vec::new();
+
+        buf.try_reserve_exact(capacity)
+            .map_err(|_| ArenaError::AllocFailed)?;
+
+        Ok(Self::from_vec(buf, start, max_slots))
+    }
+
+    /// Take over the buffer of an empty `buf`, using at most `max_slots` of its slots.
+    fn from_vec(buf: Vec<T>, start: usize, max_slots: usize) -> NonNull<Self> {
+        let mut buf = ManuallyDrop::new(buf);
+        let chunk = Box::new(SyncChunk {
+            // The pointer of a `Vec` is never null.
+            ptr: unsafe { NonNull::new_unchecked(buf.as_mut_ptr()) },
+            reserved: AtomicUsize::new(0),
+            capacity: buf.capacity().min(max_slots),
+            buf_capacity: buf.capacity(),
+            start,
+        });
+
+        NonNull::from(Box::leak(chunk))
+    }
+
+    fn len(&self) -> usize {
+        self.reserved.load(Ordering::Relaxed).min(self.capacity)
+    }
+}
+
+impl<T> UnsafeSyncArenaRef<T> {
+    fn new(ptr: *mut T) -> Self {
+        UnsafeSyncArenaRef {
+            ptr: unsafe { NonNull::new_unchecked(ptr) },
+        }
+    }
+}
+
+impl<T> Clone for UnsafeSyncArenaRef<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for UnsafeSyncArenaRef<T> {}
+
+impl<T> UnsafeSyncArena<T> {
+    /// Create an arena whose chunks hold `capacity` elements each.
+    /// A `capacity` of zero is treated as one.
+    pub fn new(capacity: usize) -> Self {
+        Self::with_policy(ChunkPolicy::Fixed(capacity))
+    }
+
+    /// Create an arena whose chunks are sized by `policy`.
+    pub fn with_policy(policy: ChunkPolicy) -> Self {
+        let chunk = SyncChunk::alloc(policy.chunk_size(0));
+
+        UnsafeSyncArena {
+            current: AtomicPtr::new(chunk.as_ptr()),
+            chunks: Mutex::new(vec![chunk]),
+            policy,
+            max_len: None,
+            max_bytes: None,
+            __marker: PhantomData,
+        }
+    }
+
+    /// Get raw pointer over `UnsafeSyncArenaRef`.
+    /// # Safety
+    /// The calling is safe if `r` is constructed by the same arena.
+    pub unsafe fn get_raw(&self, r: &UnsafeSyncArenaRef<T>) -> *mut T {
+        r.ptr.as_ptr()
+    }
+
+    /// Get primitive reference over `UnsafeSyncArenaRef`.
+    /// # Safety
+    /// The calling is safe if `r` is constructed by the same arena.
+    pub unsafe fn get<'arena>(&self, r: &UnsafeSyncArenaRef<T>) -> &'arena T {
+        &*self.get_raw(r)
+    }
+
+    /// Get mutable primitive reference over `UnsafeSyncArenaRef`.
+    /// # Safety
+    /// The calling is safe if `r` is constructed by the same arena.
+    pub unsafe fn get_mut<'arena>(&self, r: &UnsafeSyncArenaRef<T>) -> &'arena mut T {
+        &mut *self.get_raw(r)
+    }
+
+    /// # Panics
+    /// Panics if a new chunk cannot be allocated.
+    pub fn alloc(&self, t: T) -> UnsafeSyncArenaRef<T> {
+        self.try_alloc(t).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Allocate an element, or fail if a new chunk cannot be allocated or does not fit into the
+    /// limits.
+    pub fn try_alloc(&self, t: T) -> ArenaResult<UnsafeSyncArenaRef<T>> {
+        loop {
+            let current = self.current.load(Ordering::Acquire);
+            // Chunks are only freed when the arena is dropped.
+            let chunk = unsafe { &*current };
+            let elem = chunk.reserved.fetch_add(1, Ordering::Relaxed);
+
+            if elem < chunk.capacity {
+                let slot = unsafe { chunk.ptr.as_ptr().add(elem) };
+                unsafe { slot.write(t) };
+
+                return Ok(UnsafeSyncArenaRef::new(slot));
+            }
+
+            self.grow(current)?;
+        }
+    }
+
+    /// Start a new chunk unless another thread has already replaced `full`.
+    fn grow(&self, full: *mut SyncChunk<T>) -> ArenaResult<()> {
+        let mut chunks = self.chunks.lock().unwrap();
+
+        if ptr::eq(self.current.load(Ordering::Acquire), full) {
+            let chunk = self.new_chunk(&chunks, self.policy.chunk_size(chunks.len()), 1)?;
+
+            chunks.push(chunk);
+            self.current.store(chunk.as_ptr(), Ordering::Release);
+        }
+
+        Ok(())
+    }
+
+    /// Start a chunk of `size` slots after `chunks`, cut down to fit into the limits.
+    /// Fails if fewer than `min_size` slots are left.
+    fn new_chunk(
+        &self,
+        chunks: &[NonNull<SyncChunk<T>>],
+        size: usize,
+        min_size: usize,
+    ) -> ArenaResult<NonNull<SyncChunk<T>>> {
+        let last = unsafe { chunks.last().unwrap().as_ref() };
+        let start = last.start.saturating_add(last.capacity);
+        let mut size = size;
+
+        if let Some(max_len) = self.max_len {
+            size = size.min(max_len.saturating_sub(start));
+            if size < min_size {
+                return Err(ArenaError::TooManyElements(max_len));
+            }
+        }
+        if let Some(max_bytes) = self.max_bytes.filter(|_| size_of::<T>() != 0) {
+            let used = start.saturating_mul(size_of::<T>());
+
+            size = size.min(max_bytes.saturating_sub(used) / size_of::<T>());
+            if size < min_size {
+                return Err(ArenaError::TooManyBytes(max_bytes));
+            }
+        }
+
+        // Buffers may have more room than asked for, which must not be used past `max_len`.
+        let max_slots = if self.max_len.is_some() {
+            size
+        } else {
+            usize::MAX
+        };
+
+        SyncChunk::try_alloc(start, size, max_slots)
+    }
+
+    /// Limit the number of elements, or lift the limit with `None`.
+    /// Chunks are cut down so that all their slots fit into the limit, so slots left unused by
+    /// `reserve` count as well.
+    pub fn set_max_len(&mut self, max_len: Option<usize>) {
+        self.max_len = max_len;
+
+        if let Some(max_len) = max_len {
+            let chunks = self.chunks.get_mut().unwrap();
+            let chunk = unsafe { chunks.last_mut().unwrap().as_mut() };
+            let len = chunk.len();
+
+            chunk.capacity = chunk
+                .capacity
+                .min(max_len.saturating_sub(chunk.start))
+                .max(len);
+        }
+    }
+
+    /// Limit the bytes taken by chunks started from now on, or lift the limit with `None`.
+    /// Chunks are cut down to fit into the limit.
+    pub fn set_max_bytes(&mut self, max_bytes: Option<usize>) {
+        self.max_bytes = max_bytes;
+    }
+
+    pub fn len(&self) -> usize {
+        let chunks = self.chunks.lock().unwrap();
+
+        chunks
+            .iter()
+            .map(|chunk| unsafe { chunk.as_ref() }.len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Total number of slots over all chunks.
+    pub fn capacity(&self) -> usize {
+        let chunks = self.chunks.lock().unwrap();
+
+        chunks.iter().fold(0, |capacity, chunk| {
+            capacity.saturating_add(unsafe { chunk.as_ref() }.capacity)
+        })
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.lock().unwrap().len()
+    }
+
+    /// Number of elements that can still be allocated before a new chunk is started.
+    pub fn remaining_in_chunk(&self) -> usize {
+        let chunk = unsafe { &*self.current.load(Ordering::Acquire) };
+
+        chunk.capacity - chunk.len()
+    }
+
+    /// Make sure the next `additional` allocations do not start a new chunk,
+    /// as long as no other thread allocates meanwhile.
+    /// # Panics
+    /// Panics if the chunk cannot be started, see `try_reserve`.
+    pub fn reserve(&self, additional: usize) {
+        self.try_reserve(additional)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Like `reserve`, but fails if a chunk of `additional` elements does not fit into the limits
+    /// or cannot be allocated.
+    pub fn try_reserve(&self, additional: usize) -> ArenaResult<()> {
+        let mut chunks = self.chunks.lock().unwrap();
+
+        if self.remaining_in_chunk() < additional {
+            let size = additional.max(self.policy.chunk_size(chunks.len()));
+            let chunk = self.new_chunk(&chunks, size, additional)?;
+
+            chunks.push(chunk);
+            self.current.store(chunk.as_ptr(), Ordering::Release);
+        }
+
+        Ok(())
+    }
+
+    /// Release the unused slots of the last chunk.
+    pub fn shrink_to_fit(&mut self) {
+        let chunks = self.chunks.get_mut().unwrap();
+        let chunk = unsafe { chunks.last_mut().unwrap().as_mut() };
+        let len = chunk.len();
+        let mut buf = ManuallyDrop::new(unsafe {
+            Vec::from_raw_parts(chunk.ptr.as_ptr(), len, chunk.buf_capacity)
+        });
+
+        buf.shrink_to_fit();
+        // The pointer of a `Vec` is never null.
+        chunk.ptr = unsafe { NonNull::new_unchecked(buf.as_mut_ptr()) };
+        chunk.capacity = chunk.capacity.min(buf.capacity());
+        chunk.buf_capacity = buf.capacity();
+        *chunk.reserved.get_mut() = len;
+    }
+}
+
+impl<T> Drop for UnsafeSyncArena<T> {
+    fn drop(&mut self) {
+        let chunks = self.chunks.get_mut().unwrap();
+
+        for chunk in chunks.drain(..) {
+            let chunk = unsafe { Box::from_raw(chunk.as_ptr()) };
+
+            drop(unsafe {
+                Vec::from_raw_parts(chunk.ptr.as_ptr(), chunk.len(), chunk.buf_capacity)
+            });
+        }
+    }
+}
+
+impl<T> SyncArena<T> {
+    pub fn new(capacity: usize) -> Self {
+        SyncArena {
+            inner: UnsafeSyncArena::new(capacity),
+        }
+    }
+
+    pub fn with_policy(policy: ChunkPolicy) -> Self {
+        SyncArena {
+            inner: UnsafeSyncArena::with_policy(policy),
+        }
+    }
+
+    /// Limit the number of elements, or lift the limit with `None`.
+    /// Allocating past the limit panics, or fails with `try_alloc`. Slots left unused by
+    /// `reserve` count as well.
+    pub fn set_max_len(&mut self, max_len: Option<usize>) {
+        self.inner.set_max_len(max_len)
+    }
+
+    /// Limit the bytes taken by chunks started from now on, or lift the limit with `None`.
+    pub fn set_max_bytes(&mut self, max_bytes: Option<usize>) {
+        self.inner.set_max_bytes(max_bytes)
+    }
+}
+
+impl<T> Arena<T> for SyncArena<T> {
+    type Ref<'arena> = SyncArenaRef<'arena, T> where T: 'arena;
+    type MutRef<'arena> = SyncArenaMutRef<'arena, T> where T: 'arena;
+
+    fn alloc(&self, t: T) -> SyncArenaRef<'_, T> {
+        SyncArenaRef {
+            inner: self.inner.alloc(t),
+            arena: self,
+        }
+    }
+
+    fn alloc_mut(&self, t: T) -> SyncArenaMutRef<'_, T> {
+        SyncArenaMutRef {
+            inner: self.inner.alloc(t),
+            arena: self,
+        }
+    }
+
+    fn try_alloc(&self, t: T) -> ArenaResult<SyncArenaRef<'_, T>> {
+        Ok(SyncArenaRef {
+            inner: self.inner.try_alloc(t)?,
+            arena: self,
+        })
+    }
+
+    fn try_alloc_mut(&self, t: T) -> ArenaResult<SyncArenaMutRef<'_, T>> {
+        Ok(SyncArenaMutRef {
+            inner: self.inner.try_alloc(t)?,
+            arena: self,
+        })
+    }
+
+    fn copy<'arena>(&'arena self, r: &SyncArenaRef<'arena, T>) -> SyncArenaMutRef<'arena, T>
+    where
+        T: Clone,
+    {
+        self.alloc_mut(unsafe { self.inner.get(&r.inner) }.clone())
+    }
+
+    fn copy_mut<'arena>(&'arena self, r: &SyncArenaMutRef<'arena, T>) -> SyncArenaMutRef<'arena, T>
+    where
+        T: Clone,
+    {
+        self.alloc_mut(unsafe { self.inner.get(&r.inner) }.clone())
+    }
+
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    fn chunk_count(&self) -> usize {
+        self.inner.chunk_count()
+    }
+
+    fn remaining_in_chunk(&self) -> usize {
+        self.inner.remaining_in_chunk()
+    }
+
+    fn reserve(&self, additional: usize) {
+        self.inner.reserve(additional)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.inner.shrink_to_fit()
+    }
+}
+
+impl<'arena, T> Deref for SyncArenaRef<'arena, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { self.arena.inner.get(&self.inner) }
+    }
+}
+
+impl<'arena, T> Deref for SyncArenaMutRef<'arena, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { self.arena.inner.get(&self.inner) }
+    }
+}
+
+impl<'arena, T> DerefMut for SyncArenaMutRef<'arena, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { self.arena.inner.get_mut(&self.inner) }
+    }
+}
+
+impl<'arena, T> ArenaRef<'arena, T> for SyncArenaRef<'arena, T> {
+    type In = SyncArena<T>;
+
+    fn make_mut(&self) -> SyncArenaMutRef<'arena, T>
+    where
+        T: Clone,
+    {
+        self.arena.copy(self)
+    }
+
+    /// Lookups are not tracked across threads, so this always gives the ref back.
+    fn thaw(self) -> Result<SyncArenaMutRef<'arena, T>, Self> {
+        Err(self)
+    }
+}
+
+impl<'arena, T> ArenaRef<'arena, T> for SyncArenaMutRef<'arena, T> {
+    type In = SyncArena<T>;
+
+    fn make_mut(&self) -> SyncArenaMutRef<'arena, T>
+    where
+        T: Clone,
+    {
+        self.arena.copy_mut(self)
+    }
+
+    fn thaw(self) -> Result<SyncArenaMutRef<'arena, T>, Self> {
+        Ok(self)
+    }
+}
+
+impl<'arena, T> ArenaImmutRef<'arena, T> for SyncArenaRef<'arena, T> {}
+
+impl<'arena, T> ArenaMutRef<'arena, T> for SyncArenaMutRef<'arena, T> {
+    fn freeze(self) -> SyncArenaRef<'arena, T> {
+        let SyncArenaMutRef { arena, inner } = self;
+
+        SyncArenaRef { arena, inner }
+    }
+}
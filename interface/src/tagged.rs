@@ -0,0 +1,152 @@
+use core::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    num::{NonZeroU32, NonZeroU64},
+};
+
+use crate::idx::Idx;
+
+/// Word a `TaggedIdx` is packed into.
+pub trait TagWord: Copy + Eq + Ord + Hash + fmt::Debug {
+    const BITS: u32;
+
+    /// Pack `bits`, which must not be zero.
+    fn from_bits(bits: u64) -> Self;
+
+    fn bits(self) -> u64;
+}
+
+impl TagWord for NonZeroU32 {
+    const BITS: u32 = u32::BITS;
+
+    fn from_bits(bits: u64) -> Self {
+        NonZeroU32::new(bits as u32).unwrap()
+    }
+
+    fn bits(self) -> u64 {
+        self.get() as u64
+    }
+}
+
+impl TagWord for NonZeroU64 {
+    const BITS: u32 = u64::BITS;
+
+    fn from_bits(bits: u64) -> Self {
+        NonZeroU64::new(bits).unwrap()
+    }
+
+    fn bits(self) -> u64 {
+        self.get()
+    }
+}
+
+/// Enum whose arena packs the variant tag and the index of an element into one `Word`.
+/// Implemented by the derive for every enum arena.
+pub trait TaggedEnum {
+    /// Number of variants. Tags run from 1 to `VARIANTS`, so a packed word is never zero.
+    const VARIANTS: u32;
+
+    type Word: TagWord;
+}
+
+/// Compact handle of an element in an enum arena: the tag of its variant and its index in the
+/// storage of that variant, packed into one word. A unit variant allocated immutably is not
+/// stored, so it has no index.
+/// Like `Idx`, it does not borrow the arena, so it can be stored, copied and sent freely.
+pub struct TaggedIdx<E: TaggedEnum> {
+    word: E::Word,
+    __marker: PhantomData<fn() -> E>,
+}
+
+impl<E: TaggedEnum> TaggedIdx<E> {
+    /// Bits at the top of the word that hold the tag.
+    const TAG_BITS: u32 = u32::BITS - E::VARIANTS.leading_zeros();
+    const INDEX_BITS: u32 = E::Word::BITS - Self::TAG_BITS;
+    const INDEX_MASK: u64 = u64::MAX >> (u64::BITS - Self::INDEX_BITS);
+
+    /// Most elements the storage of one variant can hold, so that their index fits beside the
+    /// tag. Indices of an arena never exceed `u32` anyway.
+    pub const MAX_LEN: usize = if Self::INDEX_MASK < u32::MAX as u64 {
+        Self::INDEX_MASK as usize
+    } else {
+        u32::MAX as usize
+    };
+
+    /// Pack the `tag` of a variant with the index of a stored element, or with `None` for a unit
+    /// variant that is not stored.
+    /// # Panics
+    /// Panics if `tag` is zero or greater than `E::VARIANTS`, or if `index` does not fit beside it.
+    pub fn new(tag: u32, index: Option<Idx<E>>) -> Self {
+        assert!(
+            tag != 0 && tag <= E::VARIANTS,
+            "enum arena tag {} is out of range",
+            tag
+        );
+
+        let index = index.map_or(0, |idx| idx.raw().get() as u64);
+        assert!(
+            index <= Self::INDEX_MASK,
+            "enum arena index does not fit beside its tag"
+        );
+
+        TaggedIdx {
+            word: E::Word::from_bits(((tag as u64) << Self::INDEX_BITS) | index),
+            __marker: Default::default(),
+        }
+    }
+
+    /// Tag of the variant, from 1 to `E::VARIANTS`.
+    pub fn tag(self) -> u32 {
+        (self.word.bits() >> Self::INDEX_BITS) as u32
+    }
+
+    /// Index of the element in the storage of its variant, or `None` if it is not stored.
+    pub fn idx(self) -> Option<Idx<E>> {
+        NonZeroU32::new((self.word.bits() & Self::INDEX_MASK) as u32).map(Idx::from_raw)
+    }
+}
+
+impl<E: TaggedEnum> Clone for TaggedIdx<E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<E: TaggedEnum> Copy for TaggedIdx<E> {}
+
+impl<E: TaggedEnum> PartialEq for TaggedIdx<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.word == other.word
+    }
+}
+
+impl<E: TaggedEnum> Eq for TaggedIdx<E> {}
+
+impl<E: TaggedEnum> PartialOrd for TaggedIdx<E> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<E: TaggedEnum> Ord for TaggedIdx<E> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.word.cmp(&other.word)
+    }
+}
+
+impl<E: TaggedEnum> Hash for TaggedIdx<E> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.word.hash(state)
+    }
+}
+
+impl<E: TaggedEnum> fmt::Debug for TaggedIdx<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.idx() {
+            Some(idx) => write!(f, "TaggedIdx({}, {})", self.tag(), idx.index()),
+            None => write!(f, "TaggedIdx({})", self.tag()),
+        }
+    }
+}
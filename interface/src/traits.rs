@@ -1,11 +1,36 @@
-use std::ops::{Deref, DerefMut};
+use core::ops::{Deref, DerefMut};
+
+use crate::error::ArenaResult;
 
 pub trait ArenaRef<'arena, T>: Deref {
     type In: Arena<T> + 'arena;
 
+    /// Allocate a copy of the element and return a mutable ref to it. This always copies: `self`
+    /// is only borrowed and stays usable, so it cannot be thawed in place. Use `into_mut` to
+    /// mutate the element itself when no other ref can point to it.
     fn make_mut(&self) -> <Self::In as Arena<T>>::MutRef<'arena>
     where
         T: Clone;
+
+    /// Turn this ref into a mutable ref to the same element, if no other ref can point to it.
+    /// Otherwise, the ref is given back unchanged.
+    ///
+    /// Arenas tell conservatively whether a ref is shared: once any element was looked up or
+    /// iterated over, every element allocated before it counts as shared, until the arena is
+    /// reset or rolled back. Concurrent arenas never thaw.
+    fn thaw(self) -> Result<<Self::In as Arena<T>>::MutRef<'arena>, Self>
+    where
+        Self: Sized;
+
+    /// Like `make_mut`, but mutates the element in place when `thaw` succeeds, instead of
+    /// allocating a copy.
+    fn into_mut(self) -> <Self::In as Arena<T>>::MutRef<'arena>
+    where
+        T: Clone,
+        Self: Sized,
+    {
+        self.thaw().unwrap_or_else(|r| r.make_mut())
+    }
 }
 
 pub trait ArenaImmutRef<'arena, T>: ArenaRef<'arena, T> {}
@@ -26,6 +51,19 @@ pub trait Arena<T> {
 
     fn alloc_mut(&self, t: T) -> Self::MutRef<'_>;
 
+    fn try_alloc(&self, t: T) -> ArenaResult<Self::Ref<'_>>;
+
+    fn try_alloc_mut(&self, t: T) -> ArenaResult<Self::MutRef<'_>>;
+
+    /// Allocate the element returned by `f`. Arenas that can claim a slot before calling `f`
+    /// write its result through a pointer to that slot, which lets the optimizer build the element
+    /// there when `f` is inlined, but does not guarantee it; `f` must then not look up or iterate
+    /// the arena. To be sure a large element is written in place, write it field by field through
+    /// the `as_mut_ptr` of a slot claimed by `alloc_uninit`.
+    fn alloc_with<F: FnOnce() -> T>(&self, f: F) -> Self::Ref<'_> {
+        self.alloc(f())
+    }
+
     fn copy<'arena>(&'arena self, r: &Self::Ref<'arena>) -> Self::MutRef<'arena>
     where
         T: Clone;
@@ -38,7 +76,19 @@ pub trait Arena<T> {
 
     fn is_empty(&self) -> bool;
 
+    /// Total number of slots over all chunks.
     fn capacity(&self) -> usize;
+
+    fn chunk_count(&self) -> usize;
+
+    /// Number of elements that can still be allocated before a new chunk is started.
+    fn remaining_in_chunk(&self) -> usize;
+
+    /// Make sure the next `additional` allocations do not start a new chunk.
+    fn reserve(&self, additional: usize);
+
+    /// Release the unused slots of the last chunk.
+    fn shrink_to_fit(&mut self);
 }
 
 pub trait EnumRef<'arena, E, T>: ArenaRef<'arena, T>
@@ -0,0 +1,98 @@
+//! Interleaved allocation and access patterns, meant to be run under Miri as well.
+
+use enum_arena::*;
+
+#[derive(Clone, Debug, Arena)]
+struct Node {
+    value: u64,
+    name: String,
+}
+
+#[test]
+fn test_alloc_while_refs_alive() {
+    let arena = BasicArena::new(2);
+    let first = arena.alloc(String::from("first"));
+    let first_str: &str = &first;
+    let refs: Vec<_> = (0..7).map(|i| arena.alloc(i.to_string())).collect();
+
+    assert_eq!(first_str, "first");
+    for (i, r) in refs.iter().enumerate() {
+        assert_eq!(**r, i.to_string());
+    }
+}
+
+#[test]
+fn test_deref_mut_while_refs_alive() {
+    let arena = BasicArena::new(2);
+    let a = arena.alloc(1u64);
+    let a_ref: &u64 = &a;
+    let mut b = arena.alloc_mut(2u64);
+    let mut c = arena.alloc_mut(3u64);
+    let b_mut: &mut u64 = &mut b;
+
+    *c += 10;
+    *b_mut += 20;
+    let d = arena.alloc(4u64);
+    *c += *a_ref + *d;
+
+    assert_eq!(*a_ref, 1);
+    assert_eq!(*b, 22);
+    assert_eq!(*c, 18);
+    assert_eq!(*c.freeze() + *b.freeze(), 40);
+}
+
+#[test]
+fn test_generated_arena_interleaved() {
+    let arena = NodeArena::new(3);
+    let mut nodes = vec![];
+    let mut mut_node = arena.alloc_mut(Node {
+        value: 0,
+        name: String::from("root"),
+    });
+
+    for i in 0..10 {
+        let node = arena.alloc(Node {
+            value: i,
+            name: i.to_string(),
+        });
+        mut_node.value += node.value;
+        mut_node.name.push('+');
+        nodes.push(node);
+    }
+
+    let copy = mut_node.make_mut();
+    assert_eq!(mut_node.value, 45);
+    assert_eq!(copy.name, "root++++++++++");
+    assert!(nodes.iter().enumerate().all(|(i, n)| n.value == i as u64));
+}
+
+#[test]
+fn test_generational_reuse_while_refs_alive() {
+    let mut arena = GenerationalArena::new(2);
+    let a = arena.alloc(String::from("a"));
+    let b = arena.alloc(String::from("b"));
+
+    arena.remove(a);
+
+    let b_str = arena.get(b).unwrap();
+    let c = arena.alloc(String::from("c"));
+    let d = arena.alloc(String::from("d"));
+
+    assert_eq!(b_str, "b");
+    assert_eq!(arena[c], "c");
+    assert_eq!(arena[d], "d");
+}
+
+#[test]
+fn test_reset_drops_and_reuses() {
+    let mut arena = BasicArena::new(2);
+
+    for i in 0..5 {
+        arena.alloc(i.to_string());
+    }
+    arena.reset();
+
+    let refs: Vec<_> = (0..5).map(|i| arena.alloc(format!("new {}", i))).collect();
+    assert_eq!(*refs[4], "new 4");
+    assert_eq!(arena.len(), 5);
+}
@@ -0,0 +1,107 @@
+use std::alloc::Layout;
+use std::cell::Cell;
+use std::mem::size_of;
+use std::ptr::NonNull;
+
+use enum_arena::allocator_api2::alloc::AllocError;
+use enum_arena::*;
+
+#[derive(Clone, Debug, Arena)]
+struct Node {
+    value: u64,
+}
+
+/// Allocator that counts the buffers it has handed out and not taken back,
+/// forwarding the actual work to `Global`.
+#[derive(Clone, Copy)]
+struct Counting<'a> {
+    live: &'a Cell<usize>,
+}
+
+unsafe impl Allocator for Counting<'_> {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.live.set(self.live.get() + 1);
+        Global.allocate(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        self.live.set(self.live.get() - 1);
+        Global.deallocate(ptr, layout)
+    }
+}
+
+#[test]
+fn test_basic_arena_in() {
+    let live = Cell::new(0);
+    let mut arena = BasicArena::new_in(4, Counting { live: &live });
+    let indices: Vec<_> = (0..10u64).map(|i| arena.alloc_idx(i)).collect();
+
+    assert_eq!(live.get(), 3);
+    arena[indices[9]] += 1;
+    assert_eq!(arena[indices[9]], 10);
+
+    arena.shrink_to_fit();
+    assert_eq!(live.get(), 3);
+    assert_eq!(arena.into_iter().sum::<u64>(), 46);
+    assert_eq!(live.get(), 0);
+}
+
+#[test]
+fn test_generated_arena_in() {
+    let live = Cell::new(0);
+    let mut arena = NodeArena::with_policy_in(
+        ChunkPolicy::Doubling { initial: 2, max: 8 },
+        Counting { live: &live },
+    );
+
+    for value in 0..20 {
+        arena.alloc(Node { value });
+    }
+    assert_eq!(live.get(), 4);
+
+    arena.reset();
+    assert_eq!(live.get(), 4);
+
+    let idx = arena.alloc_idx(Node { value: 7 });
+    let frozen = arena.freeze_all();
+    assert_eq!(frozen.len(), 1);
+    assert_eq!(frozen[idx].value, 7);
+    drop(frozen);
+    assert_eq!(live.get(), 0);
+}
+
+#[test]
+fn test_failed_reserve_keeps_spare_chunk() {
+    let live = Cell::new(0);
+    let mut arena = UnsafeArena::new_in(4, Counting { live: &live });
+
+    for i in 0..12u64 {
+        arena.alloc(i);
+    }
+    arena.reset();
+    assert_eq!(live.get(), 3);
+
+    arena.set_max_bytes(Some(size_of::<[u64; 8]>()));
+    assert_eq!(
+        arena.try_reserve(6).err(),
+        Some(ArenaError::TooManyBytes(size_of::<[u64; 8]>()))
+    );
+    assert_eq!(live.get(), 3);
+
+    arena.set_max_bytes(None);
+    for i in 0..12u64 {
+        arena.alloc(i);
+    }
+    assert_eq!(live.get(), 3);
+}
+
+#[test]
+fn test_alloc_extend_in_place() {
+    let live = Cell::new(0);
+    let arena = BasicArena::new_in(8, Counting { live: &live });
+
+    arena.alloc(0u64);
+    assert_eq!(*arena.alloc_extend(1..6), [1, 2, 3, 4, 5]);
+    assert_eq!(live.get(), 1);
+    assert_eq!(arena.remaining_in_chunk(), 2);
+}
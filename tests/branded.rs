@@ -0,0 +1,18 @@
+use enum_arena::*;
+
+#[test]
+fn test_branded_arena() {
+    let sum = BasicArena::with(4, |mut arena| {
+        let indices: Vec<_> = (0..10u64).map(|i| arena.alloc(i)).collect();
+
+        for idx in &indices {
+            arena[*idx] *= 2;
+        }
+
+        assert_eq!(arena.len(), 10);
+        assert_eq!(indices[3].to_idx().index(), 3);
+        indices.iter().map(|idx| *arena.get(*idx)).sum::<u64>()
+    });
+
+    assert_eq!(sum, 90);
+}
@@ -0,0 +1,57 @@
+use std::thread;
+
+use enum_arena::*;
+
+#[derive(Clone, Debug, Arena)]
+#[concurrent]
+struct Token {
+    thread: usize,
+    seq: usize,
+}
+
+fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+#[test]
+fn test_sync_arena_parallel_alloc() {
+    let arena = SyncArena::new(8);
+
+    let refs: Vec<Vec<_>> = thread::scope(|s| {
+        let handles: Vec<_> = (0..4)
+            .map(|t| {
+                let arena = &arena;
+                s.spawn(move || (0..50).map(|i| arena.alloc(t * 100 + i)).collect())
+            })
+            .collect();
+
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    for (t, refs) in refs.iter().enumerate() {
+        assert!(refs.iter().enumerate().all(|(i, r)| **r == t * 100 + i));
+    }
+    assert_send_sync(&refs[0][0]);
+    assert_eq!(arena.len(), 200);
+}
+
+#[test]
+fn test_concurrent_derived_arena() {
+    let arena = TokenArena::new(4);
+
+    thread::scope(|s| {
+        for thread in 0..4 {
+            let arena = &arena;
+            s.spawn(move || {
+                for seq in 0..20 {
+                    let mut token = arena.alloc_mut(Token { thread, seq: 0 });
+                    token.seq = seq;
+
+                    let token = token.freeze();
+                    assert_send_sync(&token);
+                    assert_eq!((token.thread, token.seq), (thread, seq));
+                }
+            });
+        }
+    });
+
+    assert_eq!(arena.len(), 80);
+}
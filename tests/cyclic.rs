@@ -0,0 +1,53 @@
+use enum_arena::*;
+
+#[derive(Clone, Debug, Arena)]
+struct Block {
+    name: &'static str,
+    succs: Vec<Idx<Block>>,
+}
+
+#[test]
+fn test_alloc_cyclic() {
+    let arena = BlockArena::new(4);
+    let entry = arena.alloc(Block {
+        name: "entry",
+        succs: vec![],
+    });
+    let body = arena.alloc_cyclic(|body| Block {
+        name: "body",
+        succs: vec![body],
+    });
+
+    assert_eq!(body.succs, [body.to_idx()]);
+    assert_eq!(entry.name, "entry");
+    assert_eq!(arena[body.succs[0]].name, "body");
+}
+
+#[test]
+fn test_placeholder_then_fill() {
+    let arena = BlockArena::new(4);
+    let exit = arena.alloc_uninit();
+    let entry = arena.alloc(Block {
+        name: "entry",
+        succs: vec![exit.to_idx()],
+    });
+    let exit = exit.init(Block {
+        name: "exit",
+        succs: vec![entry.to_idx()],
+    });
+
+    assert_eq!(arena[entry.succs[0]].name, "exit");
+    assert_eq!(arena[exit.succs[0]].name, "entry");
+    assert_eq!(
+        arena.iter().map(|block| block.name).collect::<Vec<_>>(),
+        ["exit", "entry"]
+    );
+}
+
+#[test]
+#[should_panic(expected = "out of bounds")]
+fn test_resolve_placeholder_before_fill() {
+    let arena = BasicArena::<u64>::new(4);
+
+    arena.alloc_cyclic(|this| arena[this] + 1);
+}
@@ -1,3 +1,5 @@
+use std::mem::size_of;
+
 use enum_arena::*;
 
 #[derive(Clone, Debug, Arena)]
@@ -27,5 +29,92 @@ fn test_foo_arena() {
         assert_eq!(r.b, i as u64 + 1);
     }
     assert_eq!(arena.len(), 1024);
-    assert_eq!(arena.capacity(), 16);
+    assert_eq!(arena.capacity(), 1024);
+    assert_eq!(arena.chunk_count(), 64);
+}
+
+#[test]
+fn test_foo_ref_size() {
+    assert_eq!(size_of::<FooRef>(), 2 * size_of::<usize>());
+    assert_eq!(size_of::<FooMutRef>(), 2 * size_of::<usize>());
+}
+
+#[test]
+fn test_option_ref_size() {
+    assert_eq!(
+        size_of::<Option<UnsafeArenaRef<Foo>>>(),
+        size_of::<UnsafeArenaRef<Foo>>()
+    );
+    assert_eq!(
+        size_of::<Option<BasicArenaRef<Foo>>>(),
+        size_of::<BasicArenaRef<Foo>>()
+    );
+    assert_eq!(size_of::<Option<FooRef>>(), size_of::<FooRef>());
+    assert_eq!(size_of::<Option<FooMutRef>>(), size_of::<FooMutRef>());
+}
+
+#[test]
+#[should_panic(expected = "did not create it")]
+#[cfg_attr(not(debug_assertions), ignore)]
+fn test_ref_of_other_arena() {
+    let a = UnsafeArena::new(16);
+    let b = UnsafeArena::new(16);
+    let r = a.alloc(Foo { a: 0, b: 0 });
+
+    b.alloc(Foo { a: 1, b: 1 });
+    unsafe { b.get(&r) };
+}
+
+#[test]
+fn test_reserve_and_shrink() {
+    let mut arena = FooArena::new(4);
+    let first = arena.alloc(Foo { a: 0, b: 0 }).to_idx();
+
+    assert_eq!(arena.remaining_in_chunk(), 3);
+    arena.reserve(10);
+    assert_eq!(arena.chunk_count(), 2);
+    assert!(arena.remaining_in_chunk() >= 10);
+
+    let refs: Vec<_> = (1..11).map(|i| arena.alloc(Foo { a: i, b: i })).collect();
+    assert_eq!(arena.chunk_count(), 2);
+    assert!(refs.iter().enumerate().all(|(i, r)| r.a == i as u64 + 1));
+    drop(refs);
+
+    arena.shrink_to_fit();
+    assert_eq!(arena.capacity(), 11);
+    assert_eq!(arena.remaining_in_chunk(), 0);
+
+    let last = arena.alloc_idx(Foo { a: 11, b: 11 });
+    assert_eq!(arena.chunk_count(), 3);
+    assert_eq!(arena[first].a, 0);
+    assert_eq!(arena[last].a, 11);
+    assert_eq!(arena.len(), 12);
+}
+
+#[test]
+fn test_reset() {
+    let mut arena = FooArena::with_policy(ChunkPolicy::Doubling { initial: 2, max: 8 });
+
+    for round in 0..3u64 {
+        let refs: Vec<_> = (0..20)
+            .map(|i| arena.alloc(Foo { a: round, b: i }))
+            .collect();
+
+        assert!(refs
+            .iter()
+            .enumerate()
+            .all(|(i, r)| r.a == round && r.b == i as u64));
+        drop(refs);
+        assert_eq!(arena.len(), 20);
+        assert_eq!(arena.chunk_count(), 4);
+
+        arena.reset();
+        assert!(arena.is_empty());
+        assert_eq!(arena.chunk_count(), 1);
+        assert_eq!(arena.capacity(), 2);
+    }
+
+    let idx = arena.alloc_idx(Foo { a: 7, b: 7 });
+    assert_eq!(idx.index(), 0);
+    assert_eq!(arena[idx].b, 7);
 }
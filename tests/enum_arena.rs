@@ -0,0 +1,201 @@
+use std::mem::size_of;
+
+use enum_arena::*;
+
+#[derive(Clone, Debug, PartialEq, Arena)]
+enum Expr {
+    Lit(i64),
+    Add(u32, u32),
+    Neg { operand: u32 },
+    Unit,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Arena)]
+enum Ty {
+    Never,
+    Bool,
+    Int,
+}
+
+#[derive(Clone, Debug, PartialEq, Arena)]
+#[packed = "u64"]
+#[idx_id = "WideIdx"]
+enum Wide {
+    Small(u8),
+    Large(u64),
+    Empty,
+}
+
+#[derive(Clone, Debug, PartialEq, Arena)]
+enum Tree<T> {
+    Leaf(T),
+    Empty,
+}
+
+#[test]
+fn test_enum_arena_variants() {
+    let arena = ExprArena::new(4);
+    let lit = arena.alloc(Expr::Lit(1));
+    let add = arena.alloc(Expr::Add(0, 1));
+    let neg = arena.alloc(Expr::Neg { operand: 1 });
+    let unit = arena.alloc(Expr::Unit);
+
+    assert_eq!(*lit, Expr::Lit(1));
+    assert_eq!(*add, Expr::Add(0, 1));
+    assert_eq!(*neg, Expr::Neg { operand: 1 });
+    assert_eq!(*unit, Expr::Unit);
+    assert_eq!(arena.len(), 3);
+    assert_eq!(arena.chunk_count(), 3);
+
+    for i in 0..10 {
+        arena.alloc(Expr::Lit(i));
+    }
+    assert_eq!(arena.iter_lit().count(), 11);
+    assert_eq!(arena.iter_add().count(), 1);
+    assert_eq!(arena.remaining_in_chunk(), 1);
+}
+
+#[test]
+fn test_unit_variants_take_no_storage() {
+    let arena = ExprArena::new(4);
+    let capacity = arena.capacity();
+    let units: Vec<_> = (0..100).map(|_| arena.alloc(Expr::Unit)).collect();
+
+    assert!(units.iter().all(|r| **r == Expr::Unit));
+    assert_eq!(arena.len(), 0);
+    assert_eq!(arena.capacity(), capacity);
+    assert_eq!(arena.iter().count(), 0);
+}
+
+#[test]
+fn test_unit_only_enum_arena() {
+    let arena = TyArena::new(16);
+    let tys: Vec<_> = [Ty::Never, Ty::Bool, Ty::Int]
+        .into_iter()
+        .cycle()
+        .take(30)
+        .map(|ty| arena.alloc(ty))
+        .collect();
+
+    assert!(tys
+        .iter()
+        .zip([Ty::Never, Ty::Bool, Ty::Int].iter().cycle())
+        .all(|(r, ty)| **r == *ty));
+    assert_eq!(arena.capacity(), 0);
+    assert_eq!(arena.chunk_count(), 0);
+    assert!(arena.is_empty());
+}
+
+#[test]
+fn test_mutable_unit_variant() {
+    let mut arena = TyArena::new(16);
+    let mut ty = arena.alloc_mut(Ty::Never);
+
+    *ty = Ty::Int;
+    assert_eq!(*ty.freeze(), Ty::Int);
+    assert_eq!(arena.len(), 1);
+    assert_eq!(arena.chunk_count(), 1);
+
+    for ty in arena.iter_mut() {
+        *ty = Ty::Bool;
+    }
+    assert!(arena.iter().eq([&Ty::Bool]));
+}
+
+#[test]
+fn test_enum_ref_thaw() {
+    let arena = ExprArena::new(4);
+    let lit = arena.alloc(Expr::Lit(1));
+    let unit = arena.alloc(Expr::Unit);
+
+    let mut lit = lit.thaw().ok().unwrap();
+    *lit = Expr::Lit(2);
+    assert_eq!(*lit.freeze(), Expr::Lit(2));
+
+    // The value of a unit variant is shared, so it is copied instead.
+    let unit = unit.thaw().err().unwrap();
+    let mut copy = unit.into_mut();
+    *copy = Expr::Lit(3);
+    assert_eq!(*copy, Expr::Lit(3));
+    drop(copy);
+    assert_eq!(*arena.alloc(Expr::Unit), Expr::Unit);
+}
+
+#[test]
+fn test_generic_enum_arena() {
+    let arena = TreeArena::<String>::new(4);
+    let leaf = arena.alloc(Tree::Leaf(String::from("a")));
+    let empty = arena.alloc(Tree::Empty);
+
+    assert_eq!(*leaf, Tree::Leaf(String::from("a")));
+    assert_eq!(*empty, Tree::Empty);
+    assert!(arena.iter_leaf().eq([&Tree::Leaf(String::from("a"))]));
+}
+
+#[test]
+fn test_enum_ref_size() {
+    assert_eq!(size_of::<ExprRef>(), 2 * size_of::<usize>());
+    assert_eq!(size_of::<Option<ExprRef>>(), size_of::<ExprRef>());
+    assert_eq!(size_of::<ExprIdx>(), size_of::<u32>());
+    assert_eq!(size_of::<Option<ExprIdx>>(), size_of::<u32>());
+    assert_eq!(size_of::<Option<WideIdx>>(), size_of::<u64>());
+}
+
+#[test]
+fn test_tagged_idx_capacity() {
+    // Four variants take the top three bits, as tags run from 1 to 4.
+    assert_eq!(ExprIdx::MAX_LEN, (1 << 29) - 1);
+    assert_eq!(TyIdx::MAX_LEN, (1 << 30) - 1);
+    assert_eq!(WideIdx::MAX_LEN, u32::MAX as usize);
+}
+
+#[test]
+fn test_enum_idx() {
+    let arena = ExprArena::new(4);
+    let lit = arena.alloc(Expr::Lit(1)).to_idx();
+    let neg = arena.alloc(Expr::Neg { operand: 0 }).to_idx();
+    let unit = arena.alloc(Expr::Unit).to_idx();
+
+    assert_eq!(lit.tag(), 1);
+    assert_eq!(neg.tag(), 3);
+    assert_eq!(unit.tag(), 4);
+    assert!(unit.idx().is_none());
+    assert_eq!(arena[lit], Expr::Lit(1));
+    assert_eq!(arena[neg], Expr::Neg { operand: 0 });
+    assert_eq!(arena[unit], Expr::Unit);
+    assert_eq!(*arena.get(unit).unwrap(), Expr::Unit);
+
+    let other = ExprArena::new(4);
+    assert!(other.get(lit).is_none());
+    assert!(other.get(unit).is_some());
+}
+
+#[test]
+fn test_wide_enum_idx() {
+    let arena = WideArena::new(4);
+    let refs: Vec<_> = (0..10u8)
+        .map(|i| arena.alloc(Wide::Small(i)).to_idx())
+        .collect();
+    let large = arena.alloc_mut(Wide::Large(1)).to_idx();
+    let empty = arena.alloc_mut(Wide::Empty).freeze().to_idx();
+
+    assert!(refs
+        .iter()
+        .enumerate()
+        .all(|(i, idx)| arena[*idx] == Wide::Small(i as u8)));
+    assert_eq!(large.tag(), 2);
+    assert_eq!(arena[large], Wide::Large(1));
+    // A unit variant allocated mutably is stored like any other.
+    assert!(empty.idx().is_some());
+    assert_eq!(arena[empty], Wide::Empty);
+    assert_eq!(arena.len(), 12);
+}
+
+#[test]
+#[should_panic(expected = "out of bounds")]
+fn test_enum_idx_of_other_arena() {
+    let arena = ExprArena::new(4);
+    let lit = arena.alloc(Expr::Lit(1)).to_idx();
+
+    let _ = &ExprArena::new(4)[lit];
+}
@@ -0,0 +1,91 @@
+use std::{mem, sync::Arc, thread};
+
+use enum_arena::*;
+
+#[derive(Clone, Debug, Arena)]
+struct Item {
+    id: usize,
+}
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn test_freeze_basic_arena() {
+    let arena = BasicArena::new(4);
+    let indices: Vec<_> = (0..10usize).map(|i| arena.alloc_idx(i)).collect();
+    let frozen = Arc::new(arena.freeze_all());
+
+    let sums: Vec<usize> = (0..4)
+        .map(|_| {
+            let frozen = Arc::clone(&frozen);
+            let indices = indices.clone();
+            thread::spawn(move || indices.iter().map(|idx| frozen[*idx]).sum())
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .map(|h| h.join().unwrap())
+        .collect();
+
+    assert_eq!(sums, vec![45; 4]);
+    assert_eq!(frozen.len(), 10);
+}
+
+#[test]
+fn test_frozen_get_from_threads() {
+    let arena = ItemArena::new(4);
+    let indices: Vec<_> = (0..10)
+        .map(|id| arena.alloc(Item { id }).to_idx())
+        .collect();
+    let frozen = arena.freeze_all();
+
+    assert_send_sync::<FrozenArena<u64>>();
+    assert_send_sync::<FrozenArenaRef<'_, u64>>();
+    thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                let refs: Vec<_> = indices
+                    .iter()
+                    .map(|idx| frozen.get(*idx).unwrap())
+                    .collect();
+
+                assert!(refs.iter().enumerate().all(|(id, r)| r.id == id));
+            });
+        }
+    });
+}
+
+#[test]
+fn test_freeze_with_vacant_slots() {
+    let arena = BasicArena::new(4);
+    let abandoned = arena.alloc_uninit();
+    let first = arena.alloc_idx(String::from("first"));
+    let abandoned_idx = abandoned.to_idx();
+    drop(abandoned);
+    let forgotten = arena.alloc_uninit();
+    let forgotten_idx = forgotten.to_idx();
+    mem::forget(forgotten);
+    let last = arena.alloc_idx(String::from("last"));
+
+    let frozen = arena.freeze_all();
+    assert!(frozen.get(abandoned_idx).is_none());
+    assert!(frozen.get(forgotten_idx).is_none());
+    assert_eq!(frozen[first], "first");
+    assert_eq!(*frozen.get(last).unwrap(), "last");
+}
+
+#[test]
+fn test_freeze_derived_arena() {
+    let arena = ItemArena::new(4);
+    let first = arena.alloc(Item { id: 1 }).to_idx();
+    let mut second = arena.alloc_mut(Item { id: 0 });
+    second.id = 2;
+    let second = second.freeze().to_idx();
+
+    let frozen = arena.freeze_all();
+    let r = frozen.get(first).unwrap();
+    let copied = r;
+
+    assert_eq!(copied.id, 1);
+    assert_eq!(r.to_idx(), first);
+    assert_eq!(frozen[second].id, 2);
+}
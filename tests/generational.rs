@@ -0,0 +1,27 @@
+use enum_arena::*;
+
+#[test]
+fn test_generational_remove_and_reuse() {
+    let mut arena = GenerationalArena::new(4);
+    let a = arena.alloc(String::from("a"));
+    let b = arena.alloc(String::from("b"));
+
+    assert_eq!(arena.len(), 2);
+    assert_eq!(arena.remove(a).as_deref(), Some("a"));
+    assert_eq!(arena.remove(a), None);
+    assert_eq!(arena.get(a), None);
+    assert_eq!(arena.len(), 1);
+
+    let c = arena.alloc(String::from("c"));
+
+    assert_eq!(c.index(), a.index());
+    assert_ne!(c, a);
+    assert!(!arena.contains(a));
+    assert_eq!(arena[c], "c");
+    assert_eq!(arena[b], "b");
+
+    arena[c].push('!');
+    assert_eq!(arena.get(c).map(String::as_str), Some("c!"));
+    assert_eq!(arena.get_mut(a), None);
+    assert_eq!(arena.len(), 2);
+}
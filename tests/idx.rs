@@ -0,0 +1,66 @@
+use std::collections::HashSet;
+
+use enum_arena::*;
+
+#[derive(Clone, Debug, Arena)]
+struct Node {
+    value: u64,
+}
+
+#[test]
+fn test_basic_arena_idx() {
+    let mut arena = BasicArena::new(4);
+    let indices: Vec<Idx<u64>> = (0..10u64).map(|i| arena.alloc_idx(i)).collect();
+
+    let r = arena.alloc(10);
+    assert_eq!(*arena.get(r.to_idx()).unwrap(), 10);
+
+    for (i, idx) in indices.iter().enumerate() {
+        assert_eq!(idx.index(), i);
+        arena[*idx] += 1;
+    }
+
+    for (i, idx) in indices.iter().enumerate() {
+        assert_eq!(arena[*idx], i as u64 + 1);
+    }
+    assert_eq!(indices.iter().collect::<HashSet<_>>().len(), 10);
+}
+
+#[test]
+fn test_node_arena_idx() {
+    let mut arena = NodeArena::new(4);
+    let a = arena.alloc_idx(Node { value: 1 });
+    let b = arena.alloc(Node { value: 2 }).to_idx();
+
+    arena[a].value += 10;
+
+    assert!(a < b);
+    assert_eq!(arena[a].value, 11);
+    assert_eq!(arena.get(b).unwrap().value, 2);
+
+    let other = NodeArena::new(4);
+    assert!(other.get(a).is_none());
+}
+
+#[test]
+fn test_idx_lookup_beside_live_mut_ref() {
+    let arena = NodeArena::new(4);
+    let idx = arena.alloc_idx(Node { value: 1 });
+    let mut node = arena.alloc_mut(Node { value: 2 });
+
+    node.value += arena[idx].value;
+    assert_eq!(arena[idx].value, 1);
+
+    let idx = node.to_idx();
+    drop(node);
+    assert_eq!(arena[idx].value, 3);
+}
+
+#[test]
+#[should_panic(expected = "mutable ref to it is alive")]
+fn test_idx_lookup_with_live_mut_ref() {
+    let arena = BasicArena::new(4);
+    let mut_ref = arena.alloc_mut(2u64);
+
+    let _ = arena[mut_ref.to_idx()];
+}
@@ -0,0 +1,112 @@
+use enum_arena::*;
+
+#[derive(Clone, Debug, Arena)]
+struct Function {
+    name: String,
+    checked: bool,
+}
+
+fn function(name: &str) -> Function {
+    Function {
+        name: name.to_string(),
+        checked: false,
+    }
+}
+
+#[test]
+fn test_basic_arena_iter() {
+    let mut arena = BasicArena::with_policy(ChunkPolicy::Doubling { initial: 1, max: 4 });
+
+    for i in 0..20u64 {
+        arena.alloc(i);
+    }
+    arena.reserve(8);
+    arena.alloc(20);
+
+    assert!(arena.iter().copied().eq(0..21));
+    assert!(arena.refs().rev().map(|r| *r).eq((0..21).rev()));
+
+    for value in arena.iter_mut() {
+        *value *= 2;
+    }
+    assert!(arena.into_iter().eq((0..21).map(|i| i * 2)));
+}
+
+#[test]
+fn test_iter_while_allocating() {
+    let arena = BasicArena::new(2);
+
+    arena.alloc(String::from("a"));
+    arena.alloc(String::from("b"));
+
+    for s in arena.iter() {
+        arena.alloc(s.repeat(2));
+    }
+    assert_eq!(arena.len(), 4);
+    assert_eq!(arena.iter().last().unwrap(), "bb");
+}
+
+#[test]
+fn test_function_arena_iter() {
+    let mut arena = FunctionArena::new(3);
+
+    for name in ["main", "parse", "check", "emit"] {
+        arena.alloc(function(name));
+    }
+    for f in arena.iter_mut() {
+        f.checked = true;
+    }
+
+    let names: Vec<_> = arena.refs().map(|f| f.name.clone()).collect();
+    assert_eq!(names, ["main", "parse", "check", "emit"]);
+    assert!(arena.iter().all(|f| f.checked));
+    assert_eq!(arena.into_iter().map(|f| f.name).last().unwrap(), "emit");
+}
+
+#[test]
+#[should_panic(expected = "mutable refs of the arena are alive")]
+fn test_iter_with_live_mut_ref() {
+    let arena = FunctionArena::new(3);
+    let _f = arena.alloc_mut(function("main"));
+
+    arena.iter().count();
+}
+
+#[test]
+fn test_chunk_slices() {
+    let mut arena = BasicArena::new(4);
+
+    for i in 0..10u64 {
+        arena.alloc(i);
+    }
+
+    let lens: Vec<_> = arena.as_slices().map(|s| s.len()).collect();
+    assert_eq!(lens, [4, 4, 2]);
+    assert_eq!(
+        arena
+            .as_slices()
+            .map(|s| s.iter().sum::<u64>())
+            .sum::<u64>(),
+        45
+    );
+
+    for slice in arena.as_mut_slices() {
+        slice.reverse();
+    }
+    assert!(arena.iter().copied().eq([3, 2, 1, 0, 7, 6, 5, 4, 9, 8]));
+}
+
+#[test]
+fn test_slices_while_allocating() {
+    let arena = BasicArena::new(3);
+
+    arena.alloc(1u64);
+    arena.alloc(2);
+
+    for slice in arena.as_slices() {
+        for value in slice {
+            arena.alloc(value * 10);
+        }
+    }
+    assert!(arena.iter().copied().eq([1, 2, 10, 20]));
+}
@@ -0,0 +1,128 @@
+use std::mem::size_of;
+use std::thread;
+
+use enum_arena::*;
+
+#[derive(Clone, Debug, Arena)]
+struct Stmt {
+    line: u32,
+}
+
+#[derive(Clone, Debug, Arena)]
+#[concurrent]
+struct Event {
+    id: u32,
+}
+
+#[test]
+fn test_max_len() {
+    let mut arena = StmtArena::new(4);
+    arena.set_max_len(Some(10));
+
+    for line in 0..10 {
+        assert!(arena.try_alloc(Stmt { line }).is_ok());
+    }
+    assert_eq!(
+        arena.try_alloc(Stmt { line: 10 }).err(),
+        Some(ArenaError::TooManyElements(10))
+    );
+    assert!(arena.try_alloc_mut(Stmt { line: 10 }).is_err());
+    assert_eq!(arena.len(), 10);
+
+    arena.set_max_len(None);
+    assert_eq!(arena.try_alloc(Stmt { line: 10 }).unwrap().line, 10);
+}
+
+#[test]
+fn test_max_bytes() {
+    let mut arena = BasicArena::with_policy(ChunkPolicy::Doubling {
+        initial: 4,
+        max: 64,
+    });
+    arena.set_max_bytes(Some(10 * size_of::<u64>()));
+
+    let refs: Vec<_> = (0..10u64).map_while(|i| arena.try_alloc(i).ok()).collect();
+    assert_eq!(refs.len(), 10);
+    assert!(refs.iter().enumerate().all(|(i, r)| **r == i as u64));
+    assert_eq!(
+        arena.try_alloc(10).err(),
+        Some(ArenaError::TooManyBytes(10 * size_of::<u64>()))
+    );
+    assert_eq!(arena.capacity(), 10);
+}
+
+#[test]
+#[should_panic(expected = "cannot hold more than 1 elements")]
+fn test_alloc_past_limit() {
+    let mut arena = BasicArena::new(4);
+    arena.set_max_len(Some(1));
+
+    arena.alloc(1u8);
+    arena.alloc(2u8);
+}
+
+#[test]
+fn test_sync_arena_try_alloc() {
+    let arena = SyncArena::new(2);
+    let refs: Vec<_> = (0..5).map(|i| arena.try_alloc(i).unwrap()).collect();
+
+    assert!(refs.iter().enumerate().all(|(i, r)| **r == i));
+}
+
+#[test]
+fn test_sync_arena_max_len() {
+    let mut arena = SyncArena::new(4);
+    arena.set_max_len(Some(10));
+
+    thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| (0..5).filter(|i| arena.try_alloc(*i).is_ok()).count());
+        }
+    });
+    assert_eq!(arena.len(), 10);
+    assert_eq!(
+        arena.try_alloc(0).err(),
+        Some(ArenaError::TooManyElements(10))
+    );
+}
+
+#[test]
+fn test_sync_arena_max_len_cuts_current_chunk() {
+    let mut arena = SyncArena::new(16);
+    arena.set_max_len(Some(3));
+
+    assert!((0..3u8).all(|i| arena.try_alloc(i).is_ok()));
+    assert!(arena.try_alloc(3).is_err());
+    assert_eq!(arena.capacity(), 3);
+}
+
+#[test]
+fn test_sync_arena_max_bytes() {
+    let mut arena = SyncArena::new(4);
+    arena.set_max_bytes(Some(10 * size_of::<u64>()));
+
+    assert!((0..10u64).all(|i| arena.try_alloc(i).is_ok()));
+    assert_eq!(
+        arena.try_alloc(10).err(),
+        Some(ArenaError::TooManyBytes(10 * size_of::<u64>()))
+    );
+    assert_eq!(arena.capacity(), 10);
+}
+
+#[test]
+fn test_concurrent_arena_max_len() {
+    let mut arena = EventArena::new(4);
+    arena.set_max_len(Some(6));
+
+    let ids: Vec<_> = (0..6)
+        .map(|id| arena.try_alloc(Event { id }).unwrap().id)
+        .collect();
+    assert_eq!(ids, [0, 1, 2, 3, 4, 5]);
+    assert_eq!(
+        arena
+            .try_alloc(Event { id: 6 })
+            .err()
+            .map(|err| err.to_string()),
+        Some(ArenaError::TooManyElements(6).to_string())
+    );
+}
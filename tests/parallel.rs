@@ -0,0 +1,82 @@
+#![cfg(feature = "rayon")]
+
+use enum_arena::rayon::prelude::*;
+use enum_arena::*;
+
+#[derive(Clone, Debug, Arena)]
+struct Node {
+    value: u64,
+    lints: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Arena)]
+enum Token {
+    Int(u64),
+    Ident(String),
+    Eof,
+}
+
+#[test]
+fn test_basic_arena_par_iter() {
+    let mut arena = BasicArena::with_policy(ChunkPolicy::Doubling {
+        initial: 4,
+        max: 256,
+    });
+
+    for i in 0..10_000u64 {
+        arena.alloc(i);
+    }
+    arena.par_iter_mut().for_each(|value| *value *= 2);
+
+    assert_eq!(arena.par_iter().sum::<u64>(), 9_999 * 10_000);
+    assert!(arena.iter().copied().eq((0..10_000).map(|i| i * 2)));
+}
+
+#[test]
+fn test_node_arena_par_iter() {
+    let mut arena = NodeArena::new(64);
+
+    for value in 0..1000 {
+        arena.alloc(Node { value, lints: 0 });
+    }
+    arena
+        .par_iter_mut()
+        .filter(|node| node.value % 3 == 0)
+        .for_each(|node| node.lints += 1);
+
+    assert_eq!(arena.par_iter().map(|node| node.lints).sum::<u32>(), 334);
+}
+
+#[test]
+fn test_enum_arena_par_iter() {
+    let mut arena = TokenArena::new(16);
+
+    for i in 0..100 {
+        arena.alloc(Token::Int(i));
+        arena.alloc(Token::Ident(i.to_string()));
+    }
+    arena.alloc_mut(Token::Eof);
+    arena.par_iter_mut_int().for_each(|token| {
+        if let Token::Int(i) = token {
+            *i += 1;
+        }
+    });
+
+    assert_eq!(arena.par_iter().count(), 201);
+    assert_eq!(arena.par_iter_ident().count(), 100);
+    assert_eq!(
+        arena
+            .par_iter_int()
+            .map(|token| match token {
+                Token::Int(i) => *i,
+                _ => 0,
+            })
+            .sum::<u64>(),
+        5050
+    );
+    arena
+        .par_iter_mut()
+        .filter(|token| **token == Token::Eof)
+        .for_each(|token| *token = Token::Int(0));
+    assert!(arena.par_iter().all(|token| *token != Token::Eof));
+}
@@ -0,0 +1,88 @@
+use enum_arena::*;
+
+#[derive(Clone, Debug, Arena)]
+struct Node {
+    value: u64,
+}
+
+#[test]
+fn test_doubling_chunk_sizes() {
+    let policy = ChunkPolicy::Doubling {
+        initial: 3,
+        max: 20,
+    };
+    let sizes: Vec<_> = (0..6).map(|chunk| policy.chunk_size(chunk)).collect();
+
+    assert_eq!(sizes, [3, 6, 12, 20, 20, 20]);
+    assert_eq!(ChunkPolicy::Fixed(0).chunk_size(5), 1);
+}
+
+#[test]
+fn test_doubling_arena() {
+    let mut arena = BasicArena::with_policy(ChunkPolicy::Doubling {
+        initial: 2,
+        max: 16,
+    });
+    let refs: Vec<_> = (0..100u64).map(|i| arena.alloc(i)).collect();
+    let indices: Vec<_> = refs.iter().map(|r| r.to_idx()).collect();
+
+    assert!(refs.iter().enumerate().all(|(i, r)| **r == i as u64));
+    assert_eq!(arena.len(), 100);
+    assert_eq!(arena.capacity(), 110);
+    drop(refs);
+
+    for (i, idx) in indices.iter().enumerate() {
+        assert_eq!(idx.index(), i);
+        arena[*idx] += 1;
+    }
+    assert!(indices
+        .iter()
+        .enumerate()
+        .all(|(i, idx)| arena[*idx] == i as u64 + 1));
+}
+
+#[test]
+fn test_custom_arena() {
+    let mut arena = NodeArena::with_policy(ChunkPolicy::Custom(|chunk| chunk * 3));
+    let indices: Vec<_> = (0..50)
+        .map(|value| arena.alloc_idx(Node { value }))
+        .collect();
+
+    for idx in &indices {
+        arena[*idx].value *= 2;
+    }
+    assert!(indices
+        .iter()
+        .enumerate()
+        .all(|(i, idx)| arena[*idx].value == 2 * i as u64));
+    assert!(arena.get(indices[49]).is_some());
+}
+
+#[test]
+fn test_locate_after_reserve() {
+    for policy in [
+        ChunkPolicy::Fixed(4),
+        ChunkPolicy::Doubling {
+            initial: 2,
+            max: 16,
+        },
+    ] {
+        let mut arena = UnsafeArena::with_policy(policy);
+        let mut indices: Vec<_> = (0..6u64).map(|i| arena.alloc(i).to_idx()).collect();
+
+        arena.reserve(10);
+        indices.extend((6..100).map(|i| arena.alloc(i).to_idx()));
+        arena.shrink_to_fit();
+        indices.extend((100..150).map(|i| arena.alloc(i).to_idx()));
+
+        // Only the chunks sized by `reserve` and `shrink_to_fit` are searched.
+        assert!(indices[..4].iter().all(|idx| arena.locates_directly(*idx)));
+        assert!(!arena.locates_directly(indices[6]));
+        assert!(indices[130..]
+            .iter()
+            .all(|idx| arena.locates_directly(*idx)));
+        for (i, idx) in indices.iter().enumerate() {
+            assert_eq!(*arena.lookup_mut(*idx).unwrap(), i as u64);
+        }
+    }
+}
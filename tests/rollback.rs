@@ -0,0 +1,174 @@
+use std::panic::{self, AssertUnwindSafe};
+
+use enum_arena::*;
+
+#[derive(Clone, Debug, Arena)]
+struct Token {
+    text: String,
+}
+
+#[test]
+fn test_basic_arena_rollback() {
+    let mut arena = BasicArena::new(3);
+    let kept: Vec<_> = (0..4).map(|i| arena.alloc_idx(i.to_string())).collect();
+    let checkpoint = arena.checkpoint();
+
+    for attempt in 0..3 {
+        let refs: Vec<_> = (0..5)
+            .map(|i| arena.alloc(format!("{}/{}", attempt, i)))
+            .collect();
+
+        assert_eq!(*refs[4], format!("{}/4", attempt));
+        drop(refs);
+        assert_eq!(arena.len(), 9);
+
+        arena.rollback(checkpoint);
+        assert_eq!(arena.len(), 4);
+    }
+
+    assert!(kept
+        .iter()
+        .enumerate()
+        .all(|(i, idx)| arena[*idx] == i.to_string()));
+    assert_eq!(arena.alloc_idx(String::from("next")).index(), 4);
+}
+
+#[test]
+fn test_token_arena_nested_rollback() {
+    let mut arena = TokenArena::with_policy(ChunkPolicy::Doubling { initial: 1, max: 4 });
+    let token = |text: &str| Token {
+        text: text.to_string(),
+    };
+
+    arena.alloc(token("fn"));
+    let outer = arena.checkpoint();
+    arena.alloc(token("main"));
+    let inner = arena.checkpoint();
+    for _ in 0..10 {
+        arena.alloc(token("("));
+    }
+
+    arena.rollback(inner);
+    assert_eq!(arena.len(), 2);
+    arena.rollback(outer);
+    assert_eq!(arena.len(), 1);
+    assert!(arena.iter().map(|t| t.text.as_str()).eq(["fn"]));
+}
+
+#[test]
+#[should_panic(expected = "rolled back past the checkpoint")]
+fn test_rollback_past_checkpoint() {
+    let mut arena = BasicArena::new(4);
+    let start = arena.checkpoint();
+    arena.alloc(1u64);
+    let later = arena.checkpoint();
+
+    arena.rollback(start);
+    arena.rollback(later);
+}
+
+#[test]
+#[should_panic(expected = "reset past the checkpoint")]
+fn test_rollback_after_reset() {
+    let mut arena = BasicArena::new(4);
+    arena.alloc(1u64);
+    let checkpoint = arena.checkpoint();
+
+    arena.reset();
+    arena.alloc(2);
+    arena.alloc(3);
+    arena.rollback(checkpoint);
+}
+
+#[test]
+#[should_panic(expected = "did not create it")]
+fn test_rollback_to_other_arena_checkpoint() {
+    let other = BasicArena::<u64>::new(4);
+    let mut arena = BasicArena::new(4);
+    arena.alloc(1u64);
+
+    arena.rollback(other.checkpoint());
+}
+
+fn fold<'arena, A: Arena<u64>>(tmp: &'arena A, values: &[u64]) -> u64
+where
+    A::Ref<'arena>: std::ops::Deref<Target = u64>,
+{
+    let partial: Vec<_> = values.windows(2).map(|w| tmp.alloc(w[0] * w[1])).collect();
+
+    partial.iter().map(|r| **r).sum()
+}
+
+#[test]
+fn test_basic_arena_scope() {
+    let mut arena = BasicArena::new(2);
+    let base = arena.alloc_idx(10u64);
+
+    let folded = arena.scope(|tmp| {
+        let scratch = tmp.alloc(tmp[base] + 1);
+        *scratch + fold(&**tmp, &[1, 2, 3, 4])
+    });
+
+    assert_eq!(folded, 11 + 2 + 6 + 12);
+    assert_eq!(arena.len(), 1);
+    assert_eq!(arena[base], 10);
+}
+
+#[test]
+fn test_token_arena_scope() {
+    let mut arena = TokenArena::new(4);
+    let token = |text: &str| Token {
+        text: text.to_string(),
+    };
+
+    arena.alloc(token("let"));
+    let len = arena.scope(|tmp| {
+        let mut joined = tmp.alloc_mut(token("x"));
+        for _ in 0..10 {
+            let t = tmp.alloc(token("y"));
+            joined.text.push_str(&t.text);
+        }
+        joined.text.len()
+    });
+
+    assert_eq!(len, 11);
+    assert!(arena.iter().map(|t| t.text.as_str()).eq(["let"]));
+}
+
+#[test]
+fn test_scoped_idx() {
+    let mut arena = TokenArena::new(4);
+    let keyword = arena.alloc_idx(Token {
+        text: "fn".to_string(),
+    });
+
+    let text = arena.scope(|tmp| {
+        let name = tmp.alloc_idx(Token {
+            text: "main".to_string(),
+        });
+
+        format!("{} {}", tmp[keyword].text, tmp[name].text)
+    });
+
+    assert_eq!(text, "fn main");
+    assert_eq!(arena.len(), 1);
+}
+
+#[test]
+fn test_scope_rolls_back_on_panic() {
+    let mut arena = BasicArena::new(4);
+    arena.alloc(1u64);
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        arena.scope(|tmp| {
+            for i in 0..10u64 {
+                tmp.alloc(i);
+            }
+            panic!("scope failed");
+        })
+    }));
+
+    assert!(result.is_err());
+    assert_eq!(arena.len(), 1);
+    assert!(arena.iter().eq([&1]));
+}
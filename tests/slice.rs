@@ -0,0 +1,126 @@
+use std::cell::Cell;
+use std::mem::size_of;
+
+use enum_arena::*;
+
+#[derive(Clone, Debug, Arena)]
+struct Arg {
+    value: u64,
+}
+
+#[test]
+fn test_alloc_extend() {
+    let mut arena = BasicArena::new(4);
+    let first = arena.alloc_idx(0u64);
+    let last = {
+        let run = arena.alloc_extend(1..6);
+
+        assert_eq!(*run, [1, 2, 3, 4, 5]);
+        assert_eq!(arena.chunk_count(), 2);
+        assert_eq!(arena.remaining_in_chunk(), 0);
+        assert!(arena.alloc_extend([]).is_empty());
+
+        arena.alloc_idx(6)
+    };
+
+    assert_eq!(arena.chunk_count(), 3);
+    arena[last] += 1;
+    assert_eq!(arena[first], 0);
+    assert_eq!(arena[last], 7);
+    assert_eq!(
+        arena.iter().copied().collect::<Vec<_>>(),
+        [0, 1, 2, 3, 4, 5, 7]
+    );
+}
+
+#[test]
+fn test_alloc_extend_mut() {
+    let arena = ArgArena::new(8);
+    let other = arena.alloc(Arg { value: 10 });
+    let mut run = arena.alloc_extend_mut((0..3).map(|value| Arg { value }));
+
+    for arg in run.iter_mut() {
+        arg.value *= 2;
+    }
+
+    let run = run.freeze();
+    assert_eq!(
+        run.iter().map(|arg| arg.value).collect::<Vec<_>>(),
+        [0, 2, 4]
+    );
+    assert_eq!(other.value, 10);
+    assert_eq!(arena.len(), 4);
+}
+
+#[test]
+fn test_alloc_extend_reentrant() {
+    let arena = BasicArena::new(16);
+    let run = arena.alloc_extend((0..3u64).map(|i| *arena.alloc(i + 10) - 10));
+
+    assert_eq!(*run, [0, 1, 2]);
+    assert_eq!(arena.len(), 6);
+    assert_eq!(
+        arena.iter().copied().collect::<Vec<_>>(),
+        [10, 11, 12, 0, 1, 2]
+    );
+}
+
+#[test]
+fn test_try_alloc_extend_limits() {
+    let mut arena = ArgArena::new(4);
+    arena.set_max_len(Some(4));
+
+    let run = arena.try_alloc_extend((0..5).map(|value| Arg { value }));
+    assert_eq!(run.err(), Some(ArenaError::TooManyElements(4)));
+    assert!(arena.is_empty());
+
+    arena.set_max_len(None);
+    arena.set_max_bytes(Some(6 * size_of::<Arg>()));
+
+    let run = arena.try_alloc_extend((0..3).map(|value| Arg { value }));
+    assert_eq!(run.unwrap().len(), 3);
+    assert!(arena
+        .try_alloc_extend((0..3).map(|value| Arg { value }))
+        .is_err());
+}
+
+#[test]
+fn test_alloc_extend_without_size_hint() {
+    let arena = BasicArena::new(4);
+    arena.alloc(0u64);
+
+    let run = arena.alloc_extend((1..10).filter(|i| i % 3 != 0));
+    assert_eq!(*run, [1, 2, 4, 5, 7, 8]);
+    assert_eq!(arena.chunk_count(), 2);
+    assert_eq!(
+        arena.iter().copied().collect::<Vec<_>>(),
+        [0, 1, 2, 4, 5, 7, 8]
+    );
+}
+
+#[test]
+fn test_alloc_extend_reentrant_reserve() {
+    let arena = BasicArena::new(4);
+    let run = arena.alloc_extend((0..3u64).inspect(|_| arena.reserve(8)));
+
+    assert_eq!(*run, [0, 1, 2]);
+    arena.alloc(3);
+    assert_eq!(arena.iter().copied().collect::<Vec<_>>(), [0, 1, 2, 3]);
+}
+
+#[test]
+fn test_try_alloc_extend_size_hint_limit() {
+    let mut arena = BasicArena::new(4);
+    let visited = Cell::new(0);
+    arena.set_max_len(Some(4));
+
+    let run = arena.try_alloc_extend((0..5u64).inspect(|_| visited.set(visited.get() + 1)));
+    assert_eq!(run.err(), Some(ArenaError::TooManyElements(4)));
+    assert_eq!(visited.get(), 0);
+
+    let run = arena.try_alloc_extend((0..5u64).filter(|_| true));
+    assert_eq!(run.err(), Some(ArenaError::TooManyElements(4)));
+    assert_eq!(arena.chunk_count(), 1);
+    assert_eq!(arena.remaining_in_chunk(), 4);
+    assert!(arena.is_empty());
+}
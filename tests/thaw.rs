@@ -0,0 +1,111 @@
+use enum_arena::*;
+
+#[derive(Clone, Debug, Arena)]
+struct Expr {
+    op: &'static str,
+    args: Vec<Idx<Expr>>,
+}
+
+#[derive(Clone, Debug, Arena)]
+#[concurrent]
+struct Shared {
+    value: u64,
+}
+
+#[test]
+fn test_thaw_unique_ref_in_place() {
+    let arena = BasicArena::<String>::new(4);
+    let a = arena.alloc(String::from("a"));
+    let idx = a.to_idx();
+
+    let mut a = a.thaw().ok().unwrap();
+    a.push('!');
+
+    let a = a.freeze();
+    assert_eq!(*a, "a!");
+    assert_eq!(a.to_idx(), idx);
+    assert_eq!(arena.len(), 1);
+}
+
+#[test]
+fn test_into_mut_copies_looked_up_ref() {
+    let arena = BasicArena::<u64>::new(4);
+    let a = arena.alloc(1);
+    let b = arena.alloc(2);
+    let a_idx = a.to_idx();
+
+    let looked_up = arena.get(a_idx).unwrap();
+    let a = a.thaw().err().unwrap();
+
+    let mut a = a.into_mut();
+    *a += 10;
+    let mut b = b.into_mut();
+    *b += 10;
+
+    assert_eq!((*a, *b), (11, 12));
+    drop((a, b));
+    assert_eq!(*looked_up, 1);
+    assert_eq!(arena.len(), 3);
+}
+
+#[test]
+fn test_iteration_shares_all_elements() {
+    let arena = BasicArena::<u64>::new(4);
+    let refs: Vec<_> = (0..3).map(|i| arena.alloc(i)).collect();
+
+    assert_eq!(arena.iter().sum::<u64>(), 3);
+
+    let late = arena.alloc(3);
+    assert!(refs.into_iter().all(|r| r.thaw().is_err()));
+    assert!(late.thaw().is_ok());
+}
+
+#[test]
+fn test_reset_clears_sharing() {
+    let mut arena = BasicArena::<u64>::new(4);
+    let a = arena.alloc(1);
+    let _ = arena.get(a.to_idx());
+
+    arena.reset();
+
+    let b = arena.alloc(2);
+    assert!(b.thaw().is_ok());
+}
+
+#[test]
+fn test_derived_into_mut() {
+    let arena = ExprArena::new(4);
+    let x = arena.alloc(Expr {
+        op: "x",
+        args: vec![],
+    });
+    let neg = arena.alloc(Expr {
+        op: "neg",
+        args: vec![x.to_idx()],
+    });
+    let neg_idx = neg.to_idx();
+
+    let mut neg = neg.into_mut();
+    neg.op = "abs";
+
+    let neg = neg.freeze();
+    assert_eq!(neg.to_idx(), neg_idx);
+    assert_eq!(arena[neg.args[0]].op, "x");
+
+    let x = x.into_mut().freeze();
+    assert_ne!(x.to_idx(), neg.args[0]);
+    assert_eq!(arena.len(), 3);
+}
+
+#[test]
+fn test_concurrent_ref_never_thaws() {
+    let arena = SharedArena::new(4);
+    let a = arena.alloc(Shared { value: 1 });
+
+    let a = a.thaw().err().unwrap();
+    let mut b = a.into_mut();
+    b.value += 1;
+
+    assert_eq!(b.value, 2);
+    assert_eq!(arena.len(), 2);
+}
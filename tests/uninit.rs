@@ -0,0 +1,161 @@
+use std::{
+    cell::Cell,
+    panic::{self, AssertUnwindSafe},
+    rc::Rc,
+};
+
+use enum_arena::*;
+
+#[derive(Clone, Debug, Arena)]
+struct Node {
+    payload: [u64; 64],
+    parent: Option<Idx<Node>>,
+}
+
+/// Counts how many times it is dropped.
+struct Counted<'a>(&'a Cell<usize>);
+
+impl Drop for Counted<'_> {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() + 1);
+    }
+}
+
+#[test]
+fn test_alloc_with() {
+    let arena = BasicArena::new(4);
+    let outer = arena.alloc_with(|| *arena.alloc(1u64) + 1);
+
+    // The slot of `outer` is claimed before its closure allocates.
+    assert_eq!(outer.to_idx().index(), 0);
+    assert_eq!(*outer, 2);
+    assert_eq!(arena.iter().copied().collect::<Vec<_>>(), [2, 1]);
+}
+
+#[test]
+fn test_alloc_uninit() {
+    let arena = NodeArena::new(4);
+    let root = arena
+        .alloc_uninit()
+        .init(Node {
+            payload: [1; 64],
+            parent: None,
+        })
+        .to_idx();
+
+    let mut slot = arena.alloc_uninit();
+    unsafe {
+        let ptr = slot.as_mut_ptr();
+
+        (*ptr).payload = [2; 64];
+        (*ptr).parent = Some(root);
+    }
+    let child = unsafe { slot.assume_init() };
+
+    assert_eq!(child.payload[63], 2);
+    assert_eq!(arena.get(child.parent.unwrap()).unwrap().payload[0], 1);
+    let copy = arena.alloc_with(|| Node {
+        payload: child.payload,
+        parent: Some(root),
+    });
+    assert_eq!(copy.payload[0], 2);
+    assert_eq!(arena.len(), 3);
+}
+
+#[test]
+fn test_abandon_last_slot() {
+    let arena = BasicArena::new(4);
+    arena.alloc(String::from("a"));
+    drop(arena.alloc_uninit());
+
+    assert_eq!(arena.len(), 1);
+    assert_eq!(arena.iter().collect::<Vec<_>>(), ["a"]);
+}
+
+#[test]
+fn test_abandon_vacant_slot() {
+    let mut arena = BasicArena::new(2);
+    let slot = arena.alloc_uninit();
+    let vacant = slot.to_idx();
+    let one = arena.alloc_idx(1u64);
+
+    drop(slot);
+    arena.alloc(2);
+    assert_eq!(arena.len(), 2);
+    assert!(arena.get(vacant).is_none());
+    assert_eq!(arena[one], 1);
+    assert!(arena.iter().copied().eq([1, 2]));
+    assert!(arena.iter().rev().copied().eq([2, 1]));
+    assert_eq!(arena.iter().len(), 2);
+    assert_eq!(
+        arena.as_slices().map(|s| s.len()).collect::<Vec<_>>(),
+        [1, 1]
+    );
+
+    for value in arena.iter_mut() {
+        *value *= 10;
+    }
+    assert!(arena.into_iter().eq([10, 20]));
+}
+
+#[test]
+fn test_vacant_slot_after_panic() {
+    let value = Rc::new(0u64);
+    let arena = BasicArena::new(4);
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        arena.alloc_with(|| {
+            arena.alloc(Rc::clone(&value));
+            panic!("cannot build the parent")
+        })
+    }));
+    assert!(result.is_err());
+
+    let child = arena.alloc_idx(Rc::clone(&value));
+    assert_eq!(*arena[child], 0);
+    assert_eq!(arena.iter().count(), 2);
+    assert_eq!(Rc::strong_count(&value), 3);
+
+    drop(arena);
+    assert_eq!(Rc::strong_count(&value), 1);
+}
+
+#[test]
+fn test_vacant_slots_are_not_dropped() {
+    let drops = Cell::new(0);
+    let mut arena = BasicArena::new(2);
+
+    for _ in 0..3 {
+        let slot = arena.alloc_uninit();
+        arena.alloc(Counted(&drops));
+        drop(slot);
+    }
+
+    let checkpoint = arena.checkpoint();
+    drop(arena.alloc_uninit());
+    let slot = arena.alloc_uninit();
+    arena.alloc(Counted(&drops));
+    drop(slot);
+    arena.rollback(checkpoint);
+    assert_eq!(drops.get(), 1);
+
+    arena.reset();
+    assert_eq!(drops.get(), 4);
+
+    let slot = arena.alloc_uninit();
+    arena.alloc(Counted(&drops));
+    drop(slot);
+    drop(arena);
+    assert_eq!(drops.get(), 5);
+}
+
+#[test]
+fn test_lookup_while_uninit() {
+    let arena = BasicArena::new(4);
+    let idx = arena.alloc_idx(0u64);
+    let slot = arena.alloc_uninit();
+
+    assert_eq!(*arena.get(idx).unwrap(), 0);
+    assert!(arena.get(slot.to_idx()).is_none());
+    assert!(arena.iter().eq([&0]));
+}